@@ -0,0 +1,47 @@
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use compiler::parser::parser::Parser;
+
+const FILE_NAME: &str = "bench_expressions.txt";
+const ROUNDS: u32 = 5;
+
+/// A function returning one long expression mixing every operator, call, index and parentheses
+fn source(terms: usize) -> String {
+    let operators = ["+", "*", "-", "/"];
+    let mut expr = String::from("a");
+    for i in 0..terms {
+        let operand = match i % 4 {
+            0 => format!("{}", i),
+            1 => format!("v[{}]", i % 8),
+            2 => format!("(a - {})", i),
+            _ => format!("f(a, {})", i),
+        };
+        expr = format!("{} {} {}", expr, operators[i % operators.len()], operand);
+    }
+    format!("int f(int a, int b) {{\n    int v[8];\n    return {};\n}}\n", expr)
+}
+
+fn time(pratt: bool) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut parser = Parser::new(FILE_NAME);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        black_box(parser.parse().expect("the benchmark source is valid"));
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    println!("{:>8} {:>14} {:>14} {:>8}", "terms", "table", "pratt", "speedup");
+    for terms in [1_000, 5_000, 20_000] {
+        fs::write(FILE_NAME, source(terms)).unwrap();
+        let table = time(false);
+        let pratt = time(true);
+        let speedup = table.as_secs_f64() / pratt.as_secs_f64();
+        println!("{:>8} {:>14?} {:>14?} {:>7.1}x", terms, table, pratt, speedup);
+    }
+    fs::remove_file(FILE_NAME).unwrap();
+}
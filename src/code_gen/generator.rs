@@ -3,50 +3,107 @@ use std::fs::OpenOptions;
 use std::io::Write;
 
 use std::collections::HashMap;
-use crate::parser::ast::{ASTNode, SymbolNode};
-use crate::parser::types::Type;
+use crate::error;
+use crate::parser::program::{
+    AssignOp, BinOp, Case, Declaration, Expr, ExprKind, Field, Function, Item, ItemKind, Param, Program, Stmt, StmtKind, UnaryOp, UpdateOp,
+    Variant,
+};
+use crate::parser::span::Span;
+use crate::parser::types::{StructLayout, StructTable, Type};
+
+/// System V AMD64 integer argument registers as (64, 32, 8 bit) names
+const ARG_REGISTERS: [(&str, &str, &str); 6] = [
+    ("rdi", "edi", "dil"),
+    ("rsi", "esi", "sil"),
+    ("rdx", "edx", "dl"),
+    ("rcx", "ecx", "cl"),
+    ("r8", "r8d", "r8b"),
+    ("r9", "r9d", "r9b"),
+];
+
+/// A switch with at least this many cases, spread over at most this many values per case,
+/// jumps through a table of case addresses instead of comparing with every case
+const JUMP_TABLE_MIN_CASES: usize = 4;
+const JUMP_TABLE_MAX_SPREAD: i64 = 3;
 
 #[derive(Debug)]
 struct LocalVar {
-    offset: i32, // offset from rbp (negative)
-    size: i32,   // size in bytes
+    offset: i32, // offset from rbp (negative for locals, positive for stack arguments)
+    typ: Type,
 }
 
 #[derive(Debug)]
 struct FunctionContext {
+    name: String,
     locals: HashMap<String, LocalVar>,
     current_stack_size: i32,
+    pushed: i32, // 8-byte values currently pushed by the body, to keep calls 16-byte aligned
+    code: Vec<String>, // body instructions, written after the prologue once the frame size is known
 }
 
 impl FunctionContext {
-    fn new() -> Self {
+    fn new(name: &str) -> Self {
         Self {
+            name: name.to_string(),
             locals: HashMap::new(),
             current_stack_size: 0,
+            pushed: 0,
+            code: Vec::new(),
         }
     }
 
-    fn add_local(&mut self, name: String, size: i32) {
-        self.current_stack_size += size;
-        let offset = -self.current_stack_size;
-        self.locals.insert(name, LocalVar { offset, size });
+    fn add_local(&mut self, name: String, typ: Type, structs: &StructTable) {
+        let offset = self.reserve(typ.to_size_asm(structs), typ.alignment(structs));
+        self.locals.insert(name, LocalVar { offset, typ });
+    }
+
+    /// Reserves an aligned slot in the frame and returns its offset from rbp
+    fn reserve(&mut self, size: i32, alignment: i32) -> i32 {
+        self.current_stack_size = (self.current_stack_size + size + alignment - 1) / alignment * alignment;
+        -self.current_stack_size
+    }
+
+    /// Arguments past the sixth are passed by the caller above the return address
+    fn add_stack_param(&mut self, name: String, typ: Type, index: usize) {
+        let offset = 16 + 8 * (index - ARG_REGISTERS.len()) as i32;
+        self.locals.insert(name, LocalVar { offset, typ });
     }
 
     fn get_local(&self, name: &str) -> Option<&LocalVar> {
         self.locals.get(name)
     }
-}
 
+    /// Stack frame size, keeping rsp 16-byte aligned for calls
+    fn frame_size(&self) -> i32 {
+        (self.current_stack_size + 15) / 16 * 16
+    }
+
+    fn return_label(&self) -> String {
+        format!(".L{}_return", self.name)
+    }
+}
 
+/// Writes `<name>.code` and `<name>.data`, the two halves of one assembly file: the code refers to the
+/// globals defined in the data, so they are assembled as one unit. Read-only data used by a single
+/// function, like jump tables and string literals, is written with its code
 pub struct Generator<'a> {
-    symbols: &'a [SymbolNode],
+    program: &'a Program,
     data_file: File,
     code_file: File,
-    function_context: FunctionContext,
+    globals: HashMap<String, Type>,
+    functions: HashMap<String, Type>, // return type of every function
+    structs: StructTable,
+    enum_values: HashMap<String, i32>, // value of every enum variant
+    function_context: Option<FunctionContext>,
+    string_literals: usize,
+    labels: usize, // numbers the local labels of branches
+    break_labels: Vec<String>, // where a `break` jumps to, innermost switch last
+    bounds_checks: bool,
+    uses_bounds_error: bool, // an index was checked, so the error routine must be emitted
 }
 
 impl<'a> Generator<'a> {
-    pub fn new(symbols: &'a [SymbolNode], file_name: &str) -> Generator<'a> {
+    pub fn new(program: &'a Program, file_name: &str) -> Generator<'a> {
         let data_file = OpenOptions::new()
             .write(true)      // allow writing
             .create(true)     // create if it doesn't exist
@@ -59,7 +116,26 @@ impl<'a> Generator<'a> {
             .truncate(true)   // overwrite existing content
             .open(format!("{}.code", file_name)).expect("Could not open output file");
 
-        Generator { symbols, data_file, code_file, function_context: FunctionContext::new() }
+        Generator {
+            program,
+            data_file,
+            code_file,
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            structs: StructTable::new(),
+            enum_values: HashMap::new(),
+            function_context: None,
+            string_literals: 0,
+            labels: 0,
+            break_labels: Vec::new(),
+            bounds_checks: false,
+            uses_bounds_error: false,
+        }
+    }
+
+    /// Makes every array access check its index at runtime, exiting with an error when out of bounds
+    pub fn enable_bounds_checks(&mut self) {
+        self.bounds_checks = true;
     }
 
     pub fn write_code_line(&mut self, line: &str) {
@@ -70,45 +146,686 @@ impl<'a> Generator<'a> {
         writeln!(self.data_file, "{}", line).expect("Failed to write line to data file");
     }
 
+    /// Adds an instruction to the body of the function being generated
+    fn emit(&mut self, line: &str) {
+        let context = self.function_context.as_mut().expect("instruction outside of a function");
+        context.code.push(format!("    {}", line));
+    }
+
+    fn emit_label(&mut self, label: &str) {
+        let context = self.function_context.as_mut().expect("label outside of a function");
+        context.code.push(format!("{}:", label));
+    }
+
+    /// Adds read-only data next to the instructions of the function that use it, so that its local
+    /// label is defined in the same output, then goes back to the code
+    fn emit_read_only(&mut self, alignment: u32, label: &str, lines: &[String]) {
+        let context = self.function_context.as_mut().expect("data outside of a function");
+        context.code.push(".section .rodata".to_string());
+        context.code.push(format!(".balign {}", alignment));
+        context.code.push(format!("{}:", label));
+        context.code.extend(lines.iter().map(|line| format!("    {}", line)));
+        context.code.push(".text".to_string());
+    }
+
+    /// A label no other branch uses, `.L<name><number>`
+    fn new_label(&mut self, name: &str) -> String {
+        self.labels += 1;
+        format!(".L{}{}", name, self.labels)
+    }
+
+    fn push(&mut self, register: &str) {
+        self.emit(&format!("push {}", register));
+        self.function_context.as_mut().unwrap().pushed += 1;
+    }
+
+    fn pop(&mut self, register: &str) {
+        self.emit(&format!("pop {}", register));
+        self.function_context.as_mut().unwrap().pushed -= 1;
+    }
+
     pub fn generate(&mut self) {
-            for symbol in self.symbols {
-                match &symbol.value {
-                    ASTNode::DeclarationAssignment { typ, name, value } => {}
-                    ASTNode::Declaration { typ, name } => {self.handle_declaration(typ, name);}
-                    ASTNode::Assign { .. } => {}
-                    _ => {}
+        self.write_code_line(".intel_syntax noprefix");
+        self.write_code_line(".text");
+        self.write_data_line(".data");
+
+        // functions may be called before their definition
+        for item in &self.program.items {
+            let item = match &item.kind {
+                ItemKind::Public(item) => item,
+                _ => item,
+            };
+            if let ItemKind::Function(function) = &item.kind {
+                self.functions.insert(function.name.clone(), function.return_type.clone());
+            }
+        }
 
+        // the program's own items and the pub items of the modules it imports are visible to the linker
+        for item in &self.program.items {
+            match &item.kind {
+                ItemKind::Public(public) => self.handle_item(public, true),
+                _ => self.handle_item(item, item.module.is_none()),
+            }
+        }
+
+        if self.uses_bounds_error {
+            self.write_bounds_error();
+        }
+    }
+
+    /// Prints an error to stderr and exits with status 1, reached by failed bounds checks
+    fn write_bounds_error(&mut self) {
+        let message = "error: array index out of bounds\\n";
+        self.write_code_line("");
+        self.write_code_line(".section .rodata");
+        self.write_code_line(".Lbounds_error_message:");
+        self.write_code_line(&format!("    .ascii \"{}\"", message));
+        self.write_code_line(".text");
+        self.write_code_line(".Lbounds_error:");
+        self.write_code_line("    mov eax, 1"); // write
+        self.write_code_line("    mov edi, 2"); // stderr
+        self.write_code_line("    lea rsi, [rip + .Lbounds_error_message]");
+        self.write_code_line(&format!("    mov edx, {}", message.len() - 1));
+        self.write_code_line("    syscall");
+        self.write_code_line("    mov eax, 60"); // exit
+        self.write_code_line("    mov edi, 1");
+        self.write_code_line("    syscall");
+    }
+
+    /// Constants go to read-only data, where writing to them faults
+    fn handle_global_declaration(&mut self, declaration: &Declaration, global: bool) {
+        let Declaration { typ, name, value, constant, .. } = declaration;
+        if global {
+            self.write_data_line(&format!(".globl {}", name));
+        }
+        if *constant {
+            self.write_data_line(".section .rodata");
+            self.handle_global(typ, name, value.as_ref());
+            self.write_data_line(".data");
+        } else {
+            self.handle_global(typ, name, value.as_ref());
+        }
+    }
+
+    fn handle_global(&mut self, typ: &Type, variable_name: &str, value: Option<&Expr>) {
+        self.globals.insert(variable_name.to_string(), typ.clone());
+
+        let size = typ.to_size_asm(&self.structs);
+        self.write_data_line(&format!(".balign {}", typ.alignment(&self.structs)));
+        self.write_data_line(&format!("{}:", variable_name));
+
+        if let Some(ExprKind::String(literal)) = value.map(|value| &value.kind) {
+            self.write_data_line(&format!("    .ascii \"{}\"", literal));
+            self.write_data_line(&format!("    .zero {}", size - literal.len() as i32));
+            return;
+        }
+
+        let value = value.and_then(|value| value.evaluate_constant_in(&self.enum_values)).unwrap_or(0);
+        match size {
+            8 if matches!(typ, Type::Pointer(_)) => self.write_data_line(&format!("    .quad {}", value)),
+            4 => self.write_data_line(&format!("    .long {}", value)),
+            1 => self.write_data_line(&format!("    .byte {}", value)),
+            _ => self.write_data_line(&format!("    .zero {}", size)),
+        }
+    }
+
+    fn handle_struct(&mut self, name: &str, fields: &[Field]) {
+        let fields: Vec<(String, Type)> = fields.iter().map(|field| (field.name.clone(), field.typ.clone())).collect();
+        let layout = StructLayout::new(&fields, &self.structs);
+        self.structs.insert(name.to_string(), layout);
+    }
+
+    /// Numbers the variants, each one more than the one before unless its value is given
+    fn handle_enum(&mut self, variants: &[Variant]) {
+        let mut next = 0;
+        for Variant { name, value, .. } in variants {
+            let value = value.as_ref().and_then(|value| value.evaluate_constant_in(&self.enum_values)).unwrap_or(next);
+            self.enum_values.insert(name.clone(), value);
+            next = value.wrapping_add(1);
+        }
+    }
+
+    /// `global` items are made `.globl`, the others stay local to the assembly file
+    fn handle_item(&mut self, item: &Item, global: bool) {
+        match &item.kind {
+            ItemKind::Global(declaration) => self.handle_global_declaration(declaration, global),
+            ItemKind::Function(function) => self.handle_function(function, global),
+            ItemKind::Struct { name, fields } => self.handle_struct(name, fields),
+            ItemKind::Enum { variants, .. } => self.handle_enum(variants),
+            _ => {}
+        }
+    }
+
+    fn handle_function(&mut self, function: &Function, global: bool) {
+        let Function { name, params, body, .. } = function;
+        self.function_context = Some(FunctionContext::new(name));
+
+        self.handle_params(params);
+        for statement in body {
+            self.handle_statement(statement);
+        }
+
+        let context = self.function_context.take().unwrap();
+        self.write_code_line("");
+        if global {
+            self.write_code_line(&format!(".globl {}", context.name));
+        }
+        self.write_code_line(&format!("{}:", context.name));
+        self.write_code_line("    push rbp");
+        self.write_code_line("    mov rbp, rsp");
+        if context.frame_size() > 0 {
+            self.write_code_line(&format!("    sub rsp, {}", context.frame_size()));
+        }
+        for line in &context.code {
+            self.write_code_line(line);
+        }
+        self.write_code_line(&format!("{}:", context.return_label()));
+        self.write_code_line("    mov rsp, rbp");
+        self.write_code_line("    pop rbp");
+        self.write_code_line("    ret");
+    }
+
+    /// Saves the register arguments into the frame, following the System V AMD64 convention
+    fn handle_params(&mut self, params: &[Param]) {
+        // string arguments are the address of the caller's buffer, copied once every register is saved
+        let mut string_params = Vec::new();
+
+        for (i, Param { typ, name, span }) in params.iter().enumerate() {
+            let name = name.as_str();
+            let context = self.function_context.as_mut().unwrap();
+
+            if i >= ARG_REGISTERS.len() {
+                if *typ == Type::String {
+                    context.add_local(name.to_string(), typ.clone(), &self.structs);
+                    string_params.push((name, *span, format!("qword ptr [rbp+{}]", 16 + 8 * (i - ARG_REGISTERS.len()))));
+                } else {
+                    context.add_stack_param(name.to_string(), typ.clone(), i);
+                }
+                continue;
+            }
+
+            let (register_64, register_32, register_8) = ARG_REGISTERS[i];
+            if *typ == Type::String {
+                context.add_local(name.to_string(), typ.clone(), &self.structs);
+                let offset = context.reserve(8, 8);
+                self.emit(&format!("mov qword ptr [rbp{:+}], {}", offset, register_64));
+                string_params.push((name, *span, format!("qword ptr [rbp{:+}]", offset)));
+                continue;
+            }
+
+            context.add_local(name.to_string(), typ.clone(), &self.structs);
+            let (address, _) = self.variable_address(name, *span);
+            match typ {
+                Type::Pointer(_) => self.emit(&format!("mov qword ptr {}, {}", address, register_64)),
+                Type::Integer | Type::Enum(_) => self.emit(&format!("mov dword ptr {}, {}", address, register_32)),
+                _ => self.emit(&format!("mov byte ptr {}, {}", address, register_8)),
+            }
+        }
+
+        for (name, span, source) in string_params {
+            let (address, typ) = self.variable_address(name, span);
+            self.emit(&format!("mov rax, {}", source));
+            self.store(&address, &typ);
+        }
+    }
+
+    fn handle_statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Declaration(Declaration { typ, name, value, span, .. }) => {
+                self.handle_declaration(typ, name);
+                if let Some(value) = value {
+                    self.handle_assign(&Expr::new(ExprKind::Identifier(name.clone()), *span), value);
+                }
+            }
+            StmtKind::Assign { target, value } => self.handle_assign(target, value),
+            StmtKind::CompoundAssign { target, operation, value } => self.handle_compound_assign(target, *operation, value),
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.generate_expr(value);
+                }
+                let label = self.function_context.as_ref().unwrap().return_label();
+                self.emit(&format!("jmp {}", label));
+            }
+            StmtKind::Switch { value, cases } => self.handle_switch(value, cases),
+            StmtKind::Break => {
+                let label = self.break_labels.last().expect("break outside of a switch").clone();
+                self.emit(&format!("jmp {}", label));
+            }
+            StmtKind::Expr(expr) => {
+                self.generate_expr(expr);
+            }
+            StmtKind::Error => {}
+        }
+    }
 
+    /// Jumps to the case of the value, then lays the cases out in order so that an empty one falls through
+    fn handle_switch(&mut self, value: &Expr, cases: &[Case]) {
+        let end_label = self.new_label("switch_end");
+        let labels: Vec<String> = cases.iter().map(|_| self.new_label("case")).collect();
+        let mut default_label = end_label.clone();
+        let mut targets: Vec<(i32, String)> = Vec::new();
+        for (case, label) in cases.iter().zip(&labels) {
+            match &case.value {
+                Some(value) => {
+                    let value = value.evaluate_constant_in(&self.enum_values).expect("case values are constant");
+                    targets.push((value, label.clone()))
                 }
+                None => default_label = label.clone(),
+            }
+        }
+
+        self.generate_expr(value);
+        let min = targets.iter().map(|(value, _)| *value).min().unwrap_or(0);
+        let max = targets.iter().map(|(value, _)| *value).max().unwrap_or(0);
+        let spread = max as i64 - min as i64 + 1;
+        if targets.len() >= JUMP_TABLE_MIN_CASES && spread <= targets.len() as i64 * JUMP_TABLE_MAX_SPREAD {
+            self.generate_jump_table(&targets, min, spread, &default_label);
+        } else {
+            for (value, label) in &targets {
+                self.emit(&format!("cmp eax, {}", value));
+                self.emit(&format!("je {}", label));
             }
+            self.emit(&format!("jmp {}", default_label));
+        }
+
+        self.break_labels.push(end_label.clone());
+        for (case, label) in cases.iter().zip(&labels) {
+            self.emit_label(label);
+            for statement in &case.body {
+                self.handle_statement(statement);
+            }
+        }
+        self.break_labels.pop();
+        self.emit_label(&end_label);
+    }
+
+    /// Indexes a table holding the case address of every value from `min`, values outside go to the default
+    fn generate_jump_table(&mut self, targets: &[(i32, String)], min: i32, spread: i64, default_label: &str) {
+        let table = self.new_label("jump_table");
+        let entries: Vec<String> = (0..spread)
+            .map(|offset| {
+                let value = min as i64 + offset;
+                let label = targets
+                    .iter()
+                    .find(|(target, _)| *target as i64 == value)
+                    .map_or(default_label, |(_, label)| label.as_str());
+                format!(".quad {}", label)
+            })
+            .collect();
+        self.emit_read_only(8, &table, &entries);
+
+        // unsigned, so values below `min` wrap around and are caught too
+        self.emit(&format!("sub eax, {}", min));
+        self.emit(&format!("cmp eax, {}", spread - 1));
+        self.emit(&format!("ja {}", default_label));
+        self.emit(&format!("lea rcx, [rip + {}]", table));
+        self.emit("jmp qword ptr [rcx + rax*8]");
     }
 
-    pub fn handle_assignment(&self, symbol: &SymbolNode) {
+    pub fn handle_declaration(&mut self, typ: &Type, name: &str) {
+        let context = self.function_context.as_mut().expect("local declaration outside of a function");
+        context.add_local(name.to_string(), typ.clone(), &self.structs);
+    }
+
+    pub fn handle_assign(&mut self, name: &Expr, value: &Expr) {
+        if let ExprKind::Identifier(variable_name) = &name.kind {
+            let (address, typ) = self.variable_address(variable_name, name.span);
+            self.generate_expr(value);
+            self.store(&address, &typ);
+            return;
+        }
 
+        self.generate_expr(value);
+        self.push("rax");
+        let typ = self.generate_address(name);
+        self.emit("mov rcx, rax");
+        self.pop("rax");
+        self.store("[rcx]", &typ);
     }
 
-    pub fn handle_declaration(&mut self, typ: &Type, name: &ASTNode) {
-        if let Some(variable_name) = name.as_identifier() {
-            println!("Variable name: {}", variable_name);
-            self.function_context.add_local(variable_name.to_string(), typ.to_size_asm());
-            if let Some(local) = self.function_context.get_local(variable_name) {
-                let offset = local.offset;
-                // write rax into local variable at [rbp + offset]
-                self.write_code_line("mov rax, 0");
-                self.write_code_line(&format!("mov [rbp{}], rax", offset));
+    /// Applies the operation to the target in memory, the value being in ecx and the address in rsi
+    pub fn handle_compound_assign(&mut self, target: &Expr, operation: AssignOp, value: &Expr) {
+        self.generate_expr(value);
+        self.push("rax");
+        let typ = self.generate_address(target);
+        self.emit("mov rsi, rax");
+        self.pop("rcx");
+
+        let (width, register) = match &typ {
+            Type::Pointer(element) => {
+                // p += n moves by n elements
+                self.emit("movsxd rcx, ecx");
+                self.emit(&format!("imul rcx, rcx, {}", element.to_size_asm(&self.structs)));
+                ("qword ptr", "rcx")
+            }
+            Type::Char => ("byte ptr", "cl"),
+            _ => ("dword ptr", "ecx"),
+        };
+        let instruction = match operation {
+            AssignOp::Add => "add",
+            AssignOp::Sub => "sub",
+            AssignOp::BitAnd => "and",
+            AssignOp::BitOr => "or",
+            AssignOp::BitXor => "xor",
+            AssignOp::ShiftLeft => {
+                self.emit(&format!("sal {} [rsi], cl", width));
+                return;
+            }
+            AssignOp::ShiftRight => {
+                self.emit(&format!("sar {} [rsi], cl", width));
+                return;
+            }
+            // there is no multiplication or division into memory, so the target goes through eax
+            AssignOp::Mul | AssignOp::Div | AssignOp::Mod => {
+                self.load("[rsi]", &typ);
+                match operation {
+                    AssignOp::Mul => self.emit_operation(BinOp::Mul),
+                    AssignOp::Div => self.emit_operation(BinOp::Div),
+                    _ => {
+                        self.emit_operation(BinOp::Div);
+                        self.emit("mov eax, edx");
+                    }
+                }
+                self.store("[rsi]", &typ);
+                return;
             }
+        };
+        self.emit(&format!("{} {} [rsi], {}", instruction, width, register));
+    }
 
+    /// Applies the operation to eax and ecx, leaving the result in eax and the remainder of a division in edx
+    fn emit_operation(&mut self, operation: BinOp) {
+        match operation {
+            BinOp::Add => self.emit("add eax, ecx"),
+            BinOp::Sub => self.emit("sub eax, ecx"),
+            BinOp::Mul => self.emit("imul eax, ecx"),
+            BinOp::Div => {
+                self.emit("cdq");
+                self.emit("idiv ecx");
+            }
         }
+    }
 
+    /// Memory operand of a local or global variable, with its type
+    fn variable_address(&self, name: &str, span: Span) -> (String, Type) {
+        let local = self.function_context.as_ref().and_then(|context| context.get_local(name));
+        if let Some(local) = local {
+            return (format!("[rbp{:+}]", local.offset), local.typ.clone());
+        }
+        match self.globals.get(name) {
+            Some(typ) => (format!("[rip + {}]", name), typ.clone()),
+            None => {
+                error!("{}: code generation failed! unknown variable {}", span, name);
+                std::process::exit(1);
+            }
+        }
     }
 
+    /// Generates the address of an element or variable into rax and returns its type
+    fn generate_address(&mut self, expr: &Expr) -> Type {
+        match &expr.kind {
+            ExprKind::Identifier(name) => {
+                let (address, typ) = self.variable_address(name, expr.span);
+                self.emit(&format!("lea rax, {}", address));
+                typ
+            }
+            ExprKind::Index { array, index } => {
+                let Type::Array(element, length) = self.generate_address(array) else {
+                    error!("{}: code generation failed! {} is not an array", array.span, array);
+                    std::process::exit(1);
+                };
+                self.push("rax");
+                self.generate_expr(index);
+                self.emit("movsxd rcx, eax");
+                if self.bounds_checks {
+                    // unsigned comparison, so negative indexes are caught as well
+                    self.uses_bounds_error = true;
+                    self.emit(&format!("cmp rcx, {}", length));
+                    self.emit("jae .Lbounds_error");
+                }
+                self.pop("rax");
+                self.emit(&format!("imul rcx, rcx, {}", element.to_size_asm(&self.structs)));
+                self.emit("add rax, rcx");
+                *element
+            }
+            ExprKind::Member { object, field } => {
+                let Type::Struct(name) = self.generate_address(object) else {
+                    error!("{}: code generation failed! {} is not a struct", object.span, object);
+                    std::process::exit(1);
+                };
+                let field = self.structs[&name].get_field(field).unwrap().clone();
+                if field.offset != 0 {
+                    self.emit(&format!("add rax, {}", field.offset));
+                }
+                field.typ
+            }
+            // `*p` is stored at the address held by p
+            ExprKind::Unary { operation: UnaryOp::Deref, operand } => {
+                let Type::Pointer(target) = self.generate_expr(operand) else {
+                    error!("{}: code generation failed! {} is not a pointer", operand.span, operand);
+                    std::process::exit(1);
+                };
+                *target
+            }
+            _ => {
+                error!("{}: code generation failed! {} has no address", expr.span, expr);
+                std::process::exit(1);
+            }
+        }
+    }
 
-    pub fn handle_declaration_assignment(&self,  typ: &Type, name: &ASTNode) {
+    /// Generates the value of an expression into eax (pointers: rax, strings and arrays: their address
+    /// into rax) and returns its type
+    fn generate_expr(&mut self, expr: &Expr) -> Type {
+        match &expr.kind {
+            ExprKind::Number(_) | ExprKind::Bool(_) | ExprKind::Char(_) => {
+                let value = expr.evaluate_constant().unwrap();
+                self.emit(&format!("mov eax, {}", value));
+                expr.inferred_type().unwrap()
+            }
+            ExprKind::String(literal) => {
+                let label = format!(".Lstr{}", self.string_literals);
+                self.string_literals += 1;
+                // padded to a whole string buffer so it can be copied like a variable
+                let padding = Type::String.to_size_asm(&self.structs) - literal.len() as i32;
+                self.emit_read_only(1, &label, &[format!(".ascii \"{}\"", literal), format!(".zero {}", padding)]);
+                self.emit(&format!("lea rax, [rip + {}]", label));
+                Type::String
+            }
+            // an enum variant is its value
+            ExprKind::Identifier(name) if self.enum_values.contains_key(name) => {
+                self.emit(&format!("mov eax, {}", self.enum_values[name]));
+                Type::Integer
+            }
+            ExprKind::Identifier(name) => {
+                let (address, typ) = self.variable_address(name, expr.span);
+                self.load(&address, &typ);
+                typ
+            }
+            ExprKind::Call { name, args } => self.generate_call(name, args),
+            ExprKind::Index { .. } | ExprKind::Member { .. } => {
+                let typ = self.generate_address(expr);
+                self.load("[rax]", &typ);
+                typ
+            }
+            ExprKind::Unary { operation, operand } => match operation {
+                UnaryOp::AddressOf => Type::Pointer(Box::new(self.generate_address(operand))),
+                UnaryOp::Deref => {
+                    let typ = self.generate_address(expr);
+                    self.load("[rax]", &typ);
+                    typ
+                }
+            },
+            ExprKind::Update { operation, operand, prefix } => {
+                let typ = self.generate_address(operand);
+                self.emit("mov rcx, rax");
+                let (width, step) = match &typ {
+                    Type::Pointer(element) => ("qword ptr", element.to_size_asm(&self.structs)),
+                    Type::Char => ("byte ptr", 1),
+                    _ => ("dword ptr", 1),
+                };
+                let instruction = match operation {
+                    UpdateOp::Increment => "add",
+                    UpdateOp::Decrement => "sub",
+                };
+                // the old value is read before the update for `x++`, the new one after it for `++x`
+                if !prefix {
+                    self.load("[rcx]", &typ);
+                }
+                self.emit(&format!("{} {} [rcx], {}", instruction, width, step));
+                if *prefix {
+                    self.load("[rcx]", &typ);
+                }
+                typ
+            }
+            ExprKind::Conditional { condition, if_true, if_false } => {
+                // only the arm that is chosen is evaluated
+                let (else_label, end_label) = (self.new_label("else"), self.new_label("end"));
+                self.generate_expr(condition);
+                self.emit("test eax, eax");
+                self.emit(&format!("je {}", else_label));
+                let true_type = self.generate_expr(if_true);
+                self.emit(&format!("jmp {}", end_label));
+                self.emit_label(&else_label);
+                let false_type = self.generate_expr(if_false);
+                self.emit_label(&end_label);
+                // a char arm is already sign extended into eax
+                Type::common(&true_type, &false_type).unwrap_or(true_type)
+            }
+            ExprKind::Cast { typ, operand } => {
+                let from = self.generate_expr(operand);
+                // a char is loaded sign extended and a bool zero extended, so widening leaves eax as it is
+                match (&from, typ) {
+                    (Type::Integer, Type::Char) => self.emit("movsx eax, al"),
+                    (Type::Integer | Type::Char, Type::Bool) => {
+                        self.emit("test eax, eax");
+                        self.emit("setne al");
+                        self.emit("movzx eax, al");
+                    }
+                    _ => {}
+                }
+                typ.clone()
+            }
+            ExprKind::Binary { left, operation, right } => {
+                let left = self.generate_expr(left);
+                self.push("rax");
+                let right = self.generate_expr(right);
+                if matches!(left, Type::Pointer(_)) || matches!(right, Type::Pointer(_)) {
+                    return self.generate_pointer_arithmetic(*operation, left, right);
+                }
+                self.emit("mov ecx, eax");
+                self.pop("rax");
+                self.emit_operation(*operation);
+                left
+            }
+            _ => {
+                error!("{}: code generation failed! unsupported expression {}", expr.span, expr);
+                std::process::exit(1);
+            }
+        }
+    }
 
+    /// Pointer arithmetic counts in elements of the target type,
+    /// the left operand is on the stack and the right one in rax
+    fn generate_pointer_arithmetic(&mut self, operation: BinOp, left: Type, right: Type) -> Type {
+        let element_size = |typ: &Type, structs: &StructTable| match typ {
+            Type::Pointer(target) => target.to_size_asm(structs),
+            _ => 1,
+        };
+
+        match (&left, &right) {
+            (Type::Pointer(_), Type::Pointer(_)) => {
+                // p - q
+                self.emit("mov rcx, rax");
+                self.pop("rax");
+                self.emit("sub rax, rcx");
+                self.emit(&format!("mov ecx, {}", element_size(&left, &self.structs)));
+                self.emit("cqo");
+                self.emit("idiv rcx");
+                Type::Integer
+            }
+            (Type::Pointer(_), _) => {
+                // p + n, p - n
+                self.emit("movsxd rcx, eax");
+                self.emit(&format!("imul rcx, rcx, {}", element_size(&left, &self.structs)));
+                self.pop("rax");
+                match operation {
+                    BinOp::Sub => self.emit("sub rax, rcx"),
+                    _ => self.emit("add rax, rcx"),
+                }
+                left
+            }
+            _ => {
+                // n + p
+                self.pop("rcx");
+                self.emit("movsxd rcx, ecx");
+                self.emit(&format!("imul rcx, rcx, {}", element_size(&right, &self.structs)));
+                self.emit("add rax, rcx");
+                right
+            }
+        }
     }
 
-    pub fn handle_assign(&self, symbol: &SymbolNode) {
+    /// Calls a function with the System V AMD64 convention, the result is left in eax
+    fn generate_call(&mut self, function_name: &str, args: &[Expr]) -> Type {
+        let stack_args = args.len().saturating_sub(ARG_REGISTERS.len()) as i32;
+
+        // rsp must be 16-byte aligned at the call, once the stack arguments are pushed
+        let padding = (self.function_context.as_ref().unwrap().pushed + stack_args) % 2;
+        if padding == 1 {
+            self.emit("sub rsp, 8");
+            self.function_context.as_mut().unwrap().pushed += 1;
+        }
 
+        // pushed in reverse so that the seventh argument ends up on top of the stack
+        for arg in args.iter().rev() {
+            self.generate_expr(arg);
+            self.push("rax");
+        }
+        for (register, _, _) in ARG_REGISTERS.iter().take(args.len()) {
+            self.pop(register);
+        }
+        self.emit(&format!("call {}", function_name));
+
+        let cleanup = stack_args + padding;
+        if cleanup > 0 {
+            self.emit(&format!("add rsp, {}", 8 * cleanup));
+            self.function_context.as_mut().unwrap().pushed -= cleanup;
+        }
+
+        let return_type = self.functions.get(function_name).cloned().unwrap_or(Type::Void);
+        match return_type {
+            Type::Char => self.emit("movsx eax, al"),
+            Type::Bool => self.emit("movzx eax, al"),
+            _ => {}
+        }
+        return_type
+    }
+
+    fn load(&mut self, address: &str, typ: &Type) {
+        match typ {
+            Type::Integer | Type::Enum(_) => self.emit(&format!("mov eax, dword ptr {}", address)),
+            Type::Char => self.emit(&format!("movsx eax, byte ptr {}", address)),
+            Type::Bool => self.emit(&format!("movzx eax, byte ptr {}", address)),
+            Type::Pointer(_) => self.emit(&format!("mov rax, qword ptr {}", address)),
+            Type::String | Type::Array(..) | Type::Struct(_) => self.emit(&format!("lea rax, {}", address)),
+            Type::Void => {}
+        }
     }
 
-}
\ No newline at end of file
+    fn store(&mut self, address: &str, typ: &Type) {
+        match typ {
+            Type::Integer | Type::Enum(_) => self.emit(&format!("mov dword ptr {}, eax", address)),
+            Type::Char | Type::Bool => self.emit(&format!("mov byte ptr {}, al", address)),
+            Type::Pointer(_) => self.emit(&format!("mov qword ptr {}, rax", address)),
+            Type::String | Type::Struct(_) => {
+                // strings and structs are copied as whole buffers, including the terminating zero
+                self.emit("mov rsi, rax");
+                self.emit(&format!("lea rdi, {}", address));
+                self.emit(&format!("mov ecx, {}", typ.to_size_asm(&self.structs)));
+                self.emit("rep movsb");
+            }
+            Type::Array(..) | Type::Void => {}
+        }
+    }
+}
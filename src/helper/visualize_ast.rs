@@ -1,87 +1,255 @@
-use crate::parser::ast::ASTNode;
+use crate::parser::ast::{ASTNode, SymbolNode};
+use std::io::{self, Write};
 use crate::utils::{MAGENTA, RESET};
 
-pub fn print_ast_tree(node: &ASTNode, prefix: String, is_last: bool) {
+pub fn write_ast_tree(out: &mut dyn Write, node: &ASTNode, prefix: String, is_last: bool) -> io::Result<()> {
     let connector = if is_last { "└── " } else { "├── " };
     let extension = if is_last { "    " } else { "│   " };
 
     match node {
         ASTNode::Assign { name, value } => {
-            println!("{}{}Assign", prefix, connector);
+            writeln!(out, "{}{}Assign", prefix, connector)?;
             let new_prefix = format!("{}{}", prefix, extension);
 
-            println!("{}├── name:", new_prefix);
-            print_ast_tree(name, format!("{}│   ", new_prefix), false);
-            println!("{}└── value:", new_prefix);
-            print_ast_tree(value, format!("{}    ", new_prefix), true);
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── value:", new_prefix)?;
+            write_ast_tree(out, &value.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::CompoundAssign { target, operation, value } => {
+            writeln!(out, "{}{}CompoundAssign ({:?})", prefix, connector, operation)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+
+            writeln!(out, "{}├── target:", new_prefix)?;
+            write_ast_tree(out, &target.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── value:", new_prefix)?;
+            write_ast_tree(out, &value.value, format!("{}    ", new_prefix), true)?;
         }
 
         ASTNode::Declaration { typ, name } => {
-            println!("{}{}Declaration", prefix, connector);
+            writeln!(out, "{}{}Declaration", prefix, connector)?;
             let new_prefix = format!("{}{}", prefix, extension);
-            println!("{}├── type: {:?}", new_prefix, typ);
-            println!("{}└── name:", new_prefix);
-            print_ast_tree(name, format!("{}    ", new_prefix), true);
+            writeln!(out, "{}├── type: {:?}", new_prefix, typ)?;
+            writeln!(out, "{}└── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}    ", new_prefix), true)?;
         }
 
         ASTNode::DeclarationAssignment { typ, name, value } => {
-            println!("{}{}DeclarationAssignment", prefix, connector);
+            writeln!(out, "{}{}DeclarationAssignment", prefix, connector)?;
             let new_prefix = format!("{}{}", prefix, extension);
-            println!("{}├── type: {:?}", new_prefix, typ);
-            println!("{}├── name:", new_prefix);
-            print_ast_tree(name, format!("{}│   ", new_prefix), false);
-            println!("{}└── value:", new_prefix);
-            print_ast_tree(value, format!("{}    ", new_prefix), true);
+            writeln!(out, "{}├── type: {:?}", new_prefix, typ)?;
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── value:", new_prefix)?;
+            write_ast_tree(out, &value.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::Declarations { typ, declarations } => {
+            writeln!(out, "{}{}Declarations", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── type: {:?}", new_prefix, typ)?;
+            writeln!(out, "{}└── declarations:", new_prefix)?;
+            write_ast_list(out, declarations, format!("{}    ", new_prefix))?;
+        }
+
+        ASTNode::ConstDeclaration { declaration } => {
+            writeln!(out, "{}{}Const", prefix, connector)?;
+            write_ast_tree(out, &declaration.value, format!("{}{}", prefix, extension), true)?;
         }
 
         ASTNode::BinaryOperation { left, operation, right } => {
-            if let ASTNode::Operator(op) = operation.as_ref() {
-                println!("{}{}BinaryOperation ({:?})", prefix, connector, op);
-            } else {
-                println!("{}{}BinaryOperation", prefix, connector);
+            writeln!(out, "{}{}BinaryOperation ({:?})", prefix, connector, operation)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── left:", new_prefix)?;
+            write_ast_tree(out, &left.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── right:", new_prefix)?;
+            write_ast_tree(out, &right.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::UnaryOperation { operation, operand } => {
+            writeln!(out, "{}{}UnaryOperation ({:?})", prefix, connector, operation)?;
+            write_ast_tree(out, &operand.value, format!("{}{}", prefix, extension), true)?;
+        }
+
+        ASTNode::UpdateOperation { operation, operand, prefix: is_prefix } => {
+            let position = if *is_prefix { "prefix" } else { "postfix" };
+            writeln!(out, "{}{}UpdateOperation ({:?}, {})", prefix, connector, operation, position)?;
+            write_ast_tree(out, &operand.value, format!("{}{}", prefix, extension), true)?;
+        }
+
+        ASTNode::Conditional { condition, if_true, if_false } => {
+            writeln!(out, "{}{}Conditional", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── condition:", new_prefix)?;
+            write_ast_tree(out, &condition.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}├── if true:", new_prefix)?;
+            write_ast_tree(out, &if_true.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── if false:", new_prefix)?;
+            write_ast_tree(out, &if_false.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::Cast { typ, operand } => {
+            writeln!(out, "{}{}Cast ({:?})", prefix, connector, typ)?;
+            write_ast_tree(out, &operand.value, format!("{}{}", prefix, extension), true)?;
+        }
+
+        ASTNode::Switch { value, cases } => {
+            writeln!(out, "{}{}Switch", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── value:", new_prefix)?;
+            write_ast_tree(out, &value.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── cases:", new_prefix)?;
+            write_ast_list(out, cases, format!("{}    ", new_prefix))?;
+        }
+
+        ASTNode::Case { value, body } => {
+            let new_prefix = format!("{}{}", prefix, extension);
+            match value {
+                Some(value) => {
+                    writeln!(out, "{}{}Case", prefix, connector)?;
+                    writeln!(out, "{}├── value:", new_prefix)?;
+                    write_ast_tree(out, &value.value, format!("{}│   ", new_prefix), false)?;
+                }
+                None => writeln!(out, "{}{}Default", prefix, connector)?,
             }
+            writeln!(out, "{}└── body:", new_prefix)?;
+            write_ast_list(out, body, format!("{}    ", new_prefix))?;
+        }
+
+        ASTNode::Break() => writeln!(out, "{}{}Break", prefix, connector)?,
+
+        ASTNode::Function { return_type, name, params, body } => {
+            writeln!(out, "{}{}Function", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── return type: {:?}", new_prefix, return_type)?;
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}├── params:", new_prefix)?;
+            write_ast_list(out, params, format!("{}│   ", new_prefix))?;
+            writeln!(out, "{}└── body:", new_prefix)?;
+            write_ast_list(out, body, format!("{}    ", new_prefix))?;
+        }
+
+        ASTNode::Call { name, args } => {
+            writeln!(out, "{}{}Call", prefix, connector)?;
             let new_prefix = format!("{}{}", prefix, extension);
-            println!("{}├── left:", new_prefix);
-            print_ast_tree(left, format!("{}│   ", new_prefix), false);
-            println!("{}├── operation:", new_prefix);
-            print_ast_tree(operation, format!("{}│   ", new_prefix), false);
-            println!("{}└── right:", new_prefix);
-            print_ast_tree(right, format!("{}    ", new_prefix), true);
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── args:", new_prefix)?;
+            write_ast_list(out, args, format!("{}    ", new_prefix))?;
         }
 
-        ASTNode::Number(n) => println!("{}{}Number({})", prefix, connector, n),
-        ASTNode::String(s) => println!("{}{}String(\"{}\")", prefix, connector, s),
-        ASTNode::Bool(b) => println!("{}{}Bool({})", prefix, connector, b),
-        ASTNode::Char(c) => println!("{}{}Char({})", prefix, connector, c),
-        ASTNode::Identifier(id) => println!("{}{}Identifier(\"{}\")", prefix, connector, id),
-        ASTNode::Operator(op) => println!("{}{}Operator({:?})", prefix, connector, op),
-        ASTNode::Punctuation(p) => println!("{}{}Punctuation({:?})", prefix, connector, p),
+        ASTNode::Index { array, index } => {
+            writeln!(out, "{}{}Index", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── array:", new_prefix)?;
+            write_ast_tree(out, &array.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── index:", new_prefix)?;
+            write_ast_tree(out, &index.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::Member { object, field } => {
+            writeln!(out, "{}{}Member", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── object:", new_prefix)?;
+            write_ast_tree(out, &object.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── field:", new_prefix)?;
+            write_ast_tree(out, &field.value, format!("{}    ", new_prefix), true)?;
+        }
+
+        ASTNode::StructDeclaration { name, fields } => {
+            writeln!(out, "{}{}StructDeclaration", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── fields:", new_prefix)?;
+            write_ast_list(out, fields, format!("{}    ", new_prefix))?;
+        }
 
-        ASTNode::Expr(e) => {
-            println!("{}{}Expr", prefix, connector);
-            print_ast_tree(e, format!("{}    ", prefix), true);
+        ASTNode::EnumDeclaration { name, variants } => {
+            writeln!(out, "{}{}EnumDeclaration", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}│   ", new_prefix), false)?;
+            writeln!(out, "{}└── variants:", new_prefix)?;
+            write_ast_list(out, variants, format!("{}    ", new_prefix))?;
+        }
+
+        ASTNode::TypeAlias { name, typ } => {
+            writeln!(out, "{}{}TypeAlias", prefix, connector)?;
+            let new_prefix = format!("{}{}", prefix, extension);
+            writeln!(out, "{}├── type: {:?}", new_prefix, typ)?;
+            writeln!(out, "{}└── name:", new_prefix)?;
+            write_ast_tree(out, &name.value, format!("{}    ", new_prefix), true)?;
         }
 
-        ASTNode::Empty() => println!("{}{}Empty", prefix, connector),
+        ASTNode::Import { path } => writeln!(out, "{}{}Import(\"{}\")", prefix, connector, path)?,
+
+        ASTNode::Public { item } => {
+            writeln!(out, "{}{}Public", prefix, connector)?;
+            write_ast_tree(out, &item.value, format!("{}{}", prefix, extension), true)?;
+        }
+
+        ASTNode::EnumVariant { name, value } => {
+            writeln!(out, "{}{}EnumVariant ({})", prefix, connector, name)?;
+            if let Some(value) = value {
+                write_ast_tree(out, &value.value, format!("{}{}", prefix, extension), true)?;
+            }
+        }
+
+        ASTNode::Return { value } => {
+            writeln!(out, "{}{}Return", prefix, connector)?;
+            if let Some(value) = value {
+                write_ast_tree(out, &value.value, format!("{}{}", prefix, extension), true)?;
+            }
+        }
+
+        ASTNode::Number(n) => writeln!(out, "{}{}Number({})", prefix, connector, n)?,
+        ASTNode::String(s) => writeln!(out, "{}{}String(\"{}\")", prefix, connector, s)?,
+        ASTNode::Bool(b) => writeln!(out, "{}{}Bool({})", prefix, connector, b)?,
+        ASTNode::Char(c) => writeln!(out, "{}{}Char({})", prefix, connector, c)?,
+        ASTNode::Identifier(id) => writeln!(out, "{}{}Identifier(\"{}\")", prefix, connector, id)?,
+        ASTNode::Operator(op) => writeln!(out, "{}{}Operator({:?})", prefix, connector, op)?,
+        ASTNode::Punctuation(p) => writeln!(out, "{}{}Punctuation({:?})", prefix, connector, p)?,
+        ASTNode::Keyword(k) => writeln!(out, "{}{}Keyword({:?})", prefix, connector, k)?,
+        ASTNode::Type(typ) => writeln!(out, "{}{}Type({:?})", prefix, connector, typ)?,
+
+        ASTNode::Error() => writeln!(out, "{}{}Error", prefix, connector)?,
+        ASTNode::Empty() => writeln!(out, "{}{}Empty", prefix, connector)?,
     }
+    Ok(())
 }
 
-// Print from root
-pub fn visualize_ast(ast: &Vec<crate::parser::ast::SymbolNode>) {
-    println!("{} \n=== AST Visualization ===\n", MAGENTA);
+fn write_ast_list(out: &mut dyn Write, nodes: &[SymbolNode], prefix: String) -> io::Result<()> {
+    for (i, node) in nodes.iter().enumerate() {
+        write_ast_tree(out, &node.value, prefix.clone(), i == nodes.len() - 1)?;
+    }
+    Ok(())
+}
+
+/// Writes the trees of a list of symbols, separated by blank lines
+pub fn write_ast(out: &mut dyn Write, ast: &[SymbolNode]) -> io::Result<()> {
     for (i, symbol_node) in ast.iter().enumerate() {
         let is_last = i == ast.len() - 1;
-        print_ast_tree(&symbol_node.value, String::from(""), is_last);
+        write_ast_tree(out, &symbol_node.value, String::from(""), is_last)?;
         if !is_last {
-            println!();
+            writeln!(out)?;
         }
     }
+    Ok(())
+}
+
+// Print from root
+pub fn visualize_ast(ast: &[SymbolNode]) {
+    println!("{} \n=== AST Visualization ===\n", MAGENTA);
+    write_ast(&mut io::stdout(), ast).expect("Failed to print the AST");
     println!("\n=== End of AST ===\n {}", RESET);
 }
 
 // Print single node
 pub fn visualize_single_node(node: &ASTNode) {
     println!("\n=== AST Tree ===\n {}", MAGENTA);
-    print_ast_tree(node, String::from(""), true);
+    write_ast_tree(&mut io::stdout(), node, String::from(""), true).expect("Failed to print the AST");
     println!("\n=== End ===\n {}", RESET);
 }
@@ -1,6 +1,8 @@
 use std::fs;
 use std::io::{BufReader, Bytes, Read};
 use crate::error;
+use crate::parser::span::Span;
+use crate::parser::types::{Keyword, OperatorType};
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum SymbolKind {
@@ -11,21 +13,63 @@ pub enum SymbolKind {
     Char,
     Punctuation,
     Operator,
+    Keyword,
+    TypeIdentifier, // an identifier naming a type, told apart by the parser
+    Error,          // stands for the tokens skipped by the parser after a syntax error
     Whitespace,
 
     // AST nodes
+    Program,
     Assign,
+    CompoundAssign,
     Declaration,
     DeclarationAssignment,
     Expr,
     BinaryOperation,
+    UnaryOperation,
+    UpdateOperation,
+    Conditional,
+    Cast,
+    TypeName,
+    DeclarationHead,
+    DeclarationList,
+    DeclaratorHead,
+    Declarations,
+    ConstDeclaration,
+    FunctionHeader,
+    FunctionSignature,
+    FunctionBody,
+    Function,
+    CallHeader,
+    Call,
+    Index,
+    Member,
+    StructHeader,
+    StructDeclaration,
+    EnumHeader,
+    EnumBody,
+    EnumVariant,
+    EnumDeclaration,
+    TypeAlias,
+    Import,
+    Public,
+    Return,
+    SwitchHeader,
+    SwitchBody,
+    Switch,
+    CaseLabel,
+    Break,
+    ExpressionStatement,
+    ErrorStatement,
 
     Empty,
 }
 
 impl SymbolKind {
-    pub fn can_add_char(char: char, current_state: &SymbolKind) -> bool {
+    pub fn can_add_char(char: char, current_state: &SymbolKind, lexeme: &str) -> bool {
         match current_state {
+            // the longest operator is taken, `+=` rather than `+` followed by `=`
+            SymbolKind::Operator => OperatorType::from_str(&format!("{}{}", lexeme, char)).is_some(),
             SymbolKind::Identifier => char.is_alphanumeric() || char == '_',
             SymbolKind::Number => char.is_numeric(),
             SymbolKind::String => true,
@@ -39,10 +83,10 @@ impl SymbolKind {
             'a'..='z' | 'A'..='Z' => Some(SymbolKind::Identifier),
             '0'..='9' => Some(SymbolKind::Number),
             '"'  => Some(SymbolKind::String),
-            '+' | '-' | '*' | '/' | '=' => Some(SymbolKind::Operator),
+            '+' | '-' | '*' | '/' | '=' | '&' | '%' | '|' | '^' | '<' | '>' => Some(SymbolKind::Operator),
             '\'' => Some(SymbolKind::Char),
             ' ' | '\n' | '\t' => Some(SymbolKind::Whitespace),
-            '(' | ')' | '{' | '}' | '[' | ']' | ';' => Some(SymbolKind::Punctuation),
+            '(' | ')' | '{' | '}' | '[' | ']' | ';' | ',' | '.' | '?' | ':' => Some(SymbolKind::Punctuation),
 
             _ => None,
         }
@@ -52,15 +96,15 @@ impl SymbolKind {
 pub struct Token {
     kind: SymbolKind,
     lexeme: String,
-    line_number: u32,
+    span: Span,
 }
 
 impl Token {
-    pub fn new(kind: SymbolKind, lexeme: String, line_number: u32) -> Token {
+    pub fn new(kind: SymbolKind, lexeme: String, span: Span) -> Token {
         Token {
             kind,
             lexeme,
-            line_number,
+            span,
         }
     }
     pub fn get_kind(&self) -> SymbolKind {
@@ -69,8 +113,20 @@ impl Token {
     pub fn get_value(&self) -> String {
         self.lexeme.clone()
     }
+    /// The token as written, the quotes of a string or char included
+    pub fn get_text(&self) -> String {
+        match self.kind {
+            SymbolKind::String => format!("\"{}\"", self.lexeme),
+            SymbolKind::Char => format!("'{}'", self.lexeme),
+            _ => self.lexeme.clone(),
+        }
+    }
     pub fn get_line_number(&self) -> u32 {
-        self.line_number
+        self.span.line
+    }
+    /// From the first character of the token to its last, quotes included
+    pub fn get_span(&self) -> Span {
+        self.span
     }
 }
 #[derive(Eq, PartialEq, Debug)]
@@ -85,12 +141,13 @@ pub struct Lexer {
     current_token: Option<Token>,
     current_state: LexerState,
     current_line: u32,
+    current_column: u32,
 }
 
 impl Lexer {
     pub fn new(file_name: &str) -> Lexer {
         let file_content = BufReader::new(
-            fs::File::open(file_name).expect(format!("Error opening file {file_name}").as_str()),
+            fs::File::open(file_name).unwrap_or_else(|_| panic!("Error opening file {file_name}")),
         )
         .bytes();
         Lexer {
@@ -98,6 +155,7 @@ impl Lexer {
             current_token: None,
             current_state: LexerState::Start,
             current_line: 1,
+            current_column: 0,
         }
     }
     pub fn get_next_token(&mut self) -> Option<Token> {
@@ -108,12 +166,20 @@ impl Lexer {
             if char == '\r' {
                 continue;
             }
-            if self.current_state == LexerState::InToken {
-                if let Some(token) = self.handle_in_token(char) {
-                    return Some(token);
+            self.current_column += 1;
+            let token = match self.current_state {
+                LexerState::InToken => self.handle_in_token(char),
+                LexerState::Start => {
+                    self.start_new_token(char);
+                    None
                 }
-            } else if self.current_state == LexerState::Start {
-                self.start_new_token(char);
+            };
+            if char == '\n' {
+                self.current_line += 1;
+                self.current_column = 0;
+            }
+            if token.is_some() {
+                return token;
             }
         }
 
@@ -122,13 +188,10 @@ impl Lexer {
 
     fn handle_in_token(&mut self, char: char) -> Option<Token> {
         if let Some(current_token) = self.current_token.as_mut() {
-            if SymbolKind::can_add_char(char, &current_token.kind) {
+            if SymbolKind::can_add_char(char, &current_token.kind, &current_token.lexeme) {
                 current_token.lexeme.push(char);
+                current_token.span = current_token.span.to(Span::at(self.current_line, self.current_column));
             } else {
-                if char == '\n' {
-                    self.current_line += 1;
-                }
-
                 self.current_state = LexerState::Start;
                 let mut last_token = self.current_token.take().unwrap();
                 self.start_new_token(char);
@@ -160,7 +223,7 @@ impl Lexer {
         };
 
 
-        self.current_token = Some(Token::new(token_type, String::new(), self.current_line));
+        self.current_token = Some(Token::new(token_type, String::new(), Span::at(self.current_line, self.current_column)));
 
         if let Some(current_token) = self.current_token.as_mut() {
             current_token.lexeme.push(char);
@@ -170,24 +233,19 @@ impl Lexer {
     }
 
     fn finalize_token(&mut self) -> Option<Token> {
-        if let Some(current_token) = self.current_token.as_mut() {
-            if current_token.kind == SymbolKind::Identifier
-                && (current_token.lexeme == "true" || current_token.lexeme == "false")
-            {
-                current_token.kind = SymbolKind::Bool;
-            }
-        }
-
         let mut token = self.current_token.take()?;
         Self::update_token_before_return(&mut token);
         Some(token)
     }
 
     fn update_token_before_return(token: &mut Token) {
-        if token.get_kind() == SymbolKind::Identifier {
-            if token.lexeme == "true" || token.lexeme == "false" {
-                token.kind = SymbolKind::Bool;
-            }
+        if token.get_kind() != SymbolKind::Identifier {
+            return;
+        }
+        if token.lexeme == "true" || token.lexeme == "false" {
+            token.kind = SymbolKind::Bool;
+        } else if Keyword::from_str(&token.lexeme).is_some() {
+            token.kind = SymbolKind::Keyword;
         }
 
     }
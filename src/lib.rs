@@ -1,6 +1,10 @@
+#![allow(clippy::module_inception)]
+
 pub mod helper;
 pub mod lexer;
 pub mod parser;
 pub mod prelude;
 pub mod utils;
-mod semantics;
+pub mod semantics;
+pub mod code_gen;
+pub mod modules;
@@ -1,18 +1,39 @@
+use compiler::{code_gen, error, modules, parser, semantics};
 
-pub mod lexer;
-pub mod parser;
-pub mod utils;
-pub mod helper;
-pub mod prelude;
-pub mod semantics;
-
-pub mod code_gen;
 fn main() {
-    let mut parser = parser::parser::Parser::new("test.txt");
-    let symbols = parser.parse();
-    semantics::semantics::Semantics::new(&symbols).validate_semantics();
-    let mut generator = code_gen::generator::Generator::new(&symbols, "test");
+    let bounds_checks = std::env::args().any(|arg| arg == "--bounds-check");
+    let trace = std::env::args().find_map(|arg| match arg.as_str() {
+        "--trace" => Some(parser::trace::TraceLevel::Reductions),
+        "--trace-stack" => Some(parser::trace::TraceLevel::Stack),
+        _ => None,
+    });
+
+    let pratt = std::env::args().any(|arg| arg == "--pratt");
+    let mut loader = modules::modules::ModuleLoader::new(|parser| {
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        if let Some(level) = trace {
+            parser.enable_trace(level, Box::new(std::io::stderr()));
+        }
+    });
+    let program = match loader.load("test.txt") {
+        Ok(program) => program,
+        Err(errors) => {
+            for module_error in &errors {
+                error!("{}", module_error);
+            }
+            std::process::exit(1);
+        }
+    };
+    if let Err(semantic_error) = semantics::semantics::Semantics::new(&program).validate_semantics() {
+        error!("{}", semantic_error);
+        std::process::exit(1);
+    }
+    let mut generator = code_gen::generator::Generator::new(&program, "test");
+    if bounds_checks {
+        generator.enable_bounds_checks();
+    }
     generator.generate();
     
 }
-
@@ -0,0 +1,43 @@
+use std::fmt;
+use crate::parser::error::ParseError;
+
+/// Why the modules of a program cannot be put together, modules are named by their file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleError {
+    /// The file given to the compiler does not exist
+    NotFound { path: String },
+    /// `import "path";` where no file is found next to the importing module
+    MissingImport { module: String, line: u32, path: String },
+    /// A module that ends up importing itself, the chain starts and ends with it
+    Cycle { chain: Vec<String> },
+    /// An import after the first item of a module
+    LateImport { module: String, line: u32 },
+    /// A name that is declared in a module and also imported into it, or imported twice
+    Conflict { module: String, name: String },
+    /// Two files whose module names are the same once made valid assembly symbols
+    SameName { module: String, other: String, name: String },
+    /// A module that does not parse
+    Syntax { module: String, error: ParseError },
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleError::NotFound { path } => write!(f, "cannot find module {:?}", path),
+            ModuleError::MissingImport { module, line, path } => {
+                write!(f, "{}: Error at line: {} cannot find module {:?}", module, line, path)
+            }
+            ModuleError::Cycle { chain } => write!(f, "import cycle: {}", chain.join(" -> ")),
+            ModuleError::LateImport { module, line } => {
+                write!(f, "{}: Error at line: {} imports must come before the other items", module, line)
+            }
+            ModuleError::Conflict { module, name } => {
+                write!(f, "{}: {} is declared more than once through its imports", module, name)
+            }
+            ModuleError::SameName { module, other, name } => {
+                write!(f, "{}: module name {} is already the name of {}", module, name, other)
+            }
+            ModuleError::Syntax { module, error } => write!(f, "{}: {}", module, error),
+        }
+    }
+}
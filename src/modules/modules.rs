@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::lexer::lexer::{Lexer, SymbolKind, Token};
+use crate::parser::parser::Parser;
+use crate::parser::program::{Declaration, Expr, ExprKind, Function, Item, ItemKind, Param, Program, Variant};
+use crate::parser::types::{Keyword, Type};
+use crate::parser::visit::{
+    walk_declaration_mut, walk_expr_mut, walk_function_mut, walk_item_mut, walk_param_mut, walk_type_mut, walk_variant_mut,
+    VisitorMut,
+};
+use super::error::ModuleError;
+
+/*
+structure:
+Every module is a file, `import "math.src";` at the top of a file makes the `pub` items of math.src
+usable in it under their own names. The imports of a file are read ahead of parsing it, so that the
+type aliases it imports are known to its parser.
+Each module is parsed once and its names are renamed to the ones they have in the whole program:
+`square` of math.src becomes `math.square`, which cannot clash with a name written in any module
+and is a valid assembly symbol. The names of the first module, the program itself, are kept.
+The renamed modules are handed out as one program, every module after the modules it imports,
+their `pub` items still marked as such.
+ */
+
+/// The names a module sees or exports, mapped to their names in the whole program
+#[derive(Debug, Default, Clone)]
+struct Names {
+    values: HashMap<String, String>, // functions, globals, enum variants and type aliases
+    tags: HashMap<String, String>,   // structs and enums
+}
+
+/// What the modules importing a loaded module get from it
+#[derive(Debug, Clone)]
+struct Exports {
+    names: Names,
+    types: Vec<(String, Type)>, // the public type aliases, read as types by the importing parser
+}
+
+pub struct ModuleLoader<'a> {
+    configure: Box<dyn Fn(&mut Parser<'a>) + 'a>, // applied to the parser of every module
+    root: PathBuf,                                  // directory of the first module, names are relative to it
+    loaded: HashMap<PathBuf, Option<Exports>>, // None for a module with errors, reported once
+    loading: Vec<PathBuf>, // the chain of imports being loaded, a file found in it again is a cycle
+    prefixes: HashMap<String, PathBuf>, // the file every module name was given to
+    items: Vec<Item>,
+    errors: Vec<ModuleError>,
+}
+
+impl<'a> ModuleLoader<'a> {
+    pub fn new(configure: impl Fn(&mut Parser<'a>) + 'a) -> ModuleLoader<'a> {
+        ModuleLoader {
+            configure: Box::new(configure),
+            root: PathBuf::new(),
+            loaded: HashMap::new(),
+            loading: Vec::new(),
+            prefixes: HashMap::new(),
+            items: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Loads the file with every module it imports into one program, or every error found in them
+    pub fn load(&mut self, file_name: &str) -> Result<Program, Vec<ModuleError>> {
+        let Ok(file) = fs::canonicalize(file_name) else {
+            return Err(vec![ModuleError::NotFound { path: file_name.to_string() }]);
+        };
+        self.root = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.load_module(file);
+
+        if self.errors.is_empty() {
+            Ok(Program { items: std::mem::take(&mut self.items) })
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Loads a module after its imports, None when it or one of them has errors
+    fn load_module(&mut self, file: PathBuf) -> Option<Exports> {
+        if let Some(exports) = self.loaded.get(&file) {
+            return exports.clone();
+        }
+        if let Some(start) = self.loading.iter().position(|loading| *loading == file) {
+            let chain = self.loading[start..].iter().chain([&file]).map(|file| self.file_name(file)).collect();
+            self.errors.push(ModuleError::Cycle { chain });
+            return None;
+        }
+
+        self.loading.push(file.clone());
+        let exports = self.compile_module(&file);
+        self.loading.pop();
+        self.loaded.insert(file, exports.clone());
+        exports
+    }
+
+    fn compile_module(&mut self, file: &Path) -> Option<Exports> {
+        let module = self.file_name(file);
+        let directory = file.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let mut visible = Names::default();
+        let mut types = Vec::new();
+        let mut failed = false;
+        for (path, line) in self.read_imports(file, &module) {
+            let Ok(dependency) = fs::canonicalize(directory.join(&path)) else {
+                self.errors.push(ModuleError::MissingImport { module: module.clone(), line, path });
+                failed = true;
+                continue;
+            };
+            match self.load_module(dependency) {
+                Some(exports) => {
+                    self.merge(&mut visible, &exports.names, &module);
+                    types.extend(exports.types);
+                }
+                None => failed = true,
+            }
+        }
+        if failed {
+            return None;
+        }
+
+        let mut parser = Parser::new(&file.to_string_lossy());
+        (self.configure)(&mut parser);
+        for (name, typ) in types {
+            if !parser.add_type(&name, typ) {
+                self.errors.push(ModuleError::Conflict { module: module.clone(), name });
+            }
+        }
+        let program = match parser.parse() {
+            Ok(program) => program,
+            Err(errors) => {
+                let errors = errors.into_iter().map(|error| ModuleError::Syntax { module: module.clone(), error });
+                self.errors.extend(errors);
+                return None;
+            }
+        };
+
+        // the first module is the program itself, its names are kept
+        let prefix = (self.loading.len() > 1).then(|| self.module_name(file));
+        if let Some(prefix) = &prefix {
+            // `a-b.src` and `a_b.src` are both `a_b`, their symbols would be the same
+            match self.prefixes.get(prefix) {
+                Some(other) => {
+                    let other = self.file_name(other);
+                    self.errors.push(ModuleError::SameName { module, other, name: prefix.clone() });
+                    return None;
+                }
+                None => {
+                    self.prefixes.insert(prefix.clone(), file.to_path_buf());
+                }
+            }
+        }
+        let mut declared = Names::default();
+        let mut exported = Names::default();
+        for item in &program.items {
+            let item = match &item.kind {
+                ItemKind::Public(item) => {
+                    declare(item, &mut exported, prefix.as_deref());
+                    item.as_ref()
+                }
+                _ => item,
+            };
+            declare(item, &mut declared, prefix.as_deref());
+        }
+        self.merge(&mut visible, &declared, &module);
+
+        let mut exported_types = Vec::new();
+        for item in program.items {
+            let span = item.span;
+            let (public, mut item) = match item.kind {
+                ItemKind::Import { .. } => continue,
+                ItemKind::Public(item) => (true, *item),
+                kind => (false, Item::new(kind, span)),
+            };
+            let alias = match &item.kind {
+                ItemKind::TypeAlias { name, .. } if public => Some(name.clone()),
+                _ => None,
+            };
+            Rename { names: &visible }.visit_item_mut(&mut item);
+            if let (Some(alias), ItemKind::TypeAlias { typ, .. }) = (alias, &item.kind) {
+                exported_types.push((alias, typ.clone()));
+            }
+            // pub is kept for the generator, which leaves the other items of a module local to it
+            if public {
+                item = Item::new(ItemKind::Public(Box::new(item)), span);
+            }
+            item.module = prefix.clone();
+            self.items.push(item);
+        }
+
+        Some(Exports { names: exported, types: exported_types })
+    }
+
+    /// Reads the `import "path";` items at the top of a file, later imports are reported
+    fn read_imports(&mut self, file: &Path, module: &str) -> Vec<(String, u32)> {
+        let mut lexer = Lexer::new(&file.to_string_lossy());
+        let tokens: Vec<Token> = std::iter::from_fn(|| lexer.get_next_token())
+            .filter(|token| token.get_kind() != SymbolKind::Whitespace)
+            .collect();
+
+        let mut imports = Vec::new();
+        let mut rest = tokens.as_slice();
+        while let [import, path, semicolon, tail @ ..] = rest {
+            if !is_import(import) || path.get_kind() != SymbolKind::String || semicolon.get_value() != ";" {
+                break;
+            }
+            imports.push((path.get_value(), import.get_line_number()));
+            rest = tail;
+        }
+        // a malformed import is left to the parser
+        for token in rest.iter().skip(1).filter(|token| is_import(token)) {
+            self.errors.push(ModuleError::LateImport { module: module.to_string(), line: token.get_line_number() });
+        }
+        imports
+    }
+
+    /// Makes the names of `from` visible in `names`, a name that already stands for another one is a conflict
+    fn merge(&mut self, names: &mut Names, from: &Names, module: &str) {
+        for (visible, added) in [(&mut names.values, &from.values), (&mut names.tags, &from.tags)] {
+            for (name, mangled) in added {
+                match visible.get(name) {
+                    Some(existing) if existing != mangled => {
+                        self.errors.push(ModuleError::Conflict { module: module.to_string(), name: name.clone() });
+                    }
+                    _ => {
+                        visible.insert(name.clone(), mangled.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// The file as written in messages, relative to the first module
+    fn file_name(&self, file: &Path) -> String {
+        file.strip_prefix(&self.root).unwrap_or(file).to_string_lossy().into_owned()
+    }
+
+    /// `lib/math.src` is `lib.math`, every part made a valid assembly symbol
+    fn module_name(&self, file: &Path) -> String {
+        let relative = file.strip_prefix(&self.root).unwrap_or(file).with_extension("");
+        relative
+            .iter()
+            .map(|part| {
+                let part: String = part
+                    .to_string_lossy()
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+                    .collect();
+                if part.starts_with(|c: char| c.is_ascii_digit()) { format!("_{}", part) } else { part }
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+fn is_import(token: &Token) -> bool {
+    token.get_kind() == SymbolKind::Keyword && Keyword::from_str(&token.get_value()) == Some(Keyword::Import)
+}
+
+fn mangle(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}.{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+/// Adds the names a top-level item declares
+fn declare(item: &Item, names: &mut Names, prefix: Option<&str>) {
+    let mut value = |name: &str| {
+        names.values.insert(name.to_string(), mangle(prefix, name));
+    };
+    match &item.kind {
+        ItemKind::Global(Declaration { name, .. }) | ItemKind::TypeAlias { name, .. } => value(name),
+        ItemKind::Function(function) => value(&function.name),
+        ItemKind::Enum { variants, .. } => variants.iter().for_each(|variant| value(&variant.name)),
+        _ => {}
+    }
+    if let ItemKind::Struct { name, .. } | ItemKind::Enum { name, .. } = &item.kind {
+        names.tags.insert(name.clone(), mangle(prefix, name));
+    }
+}
+
+fn rename_name(name: &mut String, names: &HashMap<String, String>) {
+    if let Some(mangled) = names.get(name) {
+        *name = mangled.clone();
+    }
+}
+
+/// Gives every name in an item the name it has in the whole program.
+/// Locals are renamed as well when they share a name with a visible one, which is rejected anyway
+struct Rename<'n> {
+    names: &'n Names,
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        match &mut item.kind {
+            ItemKind::Struct { name, .. } | ItemKind::Enum { name, .. } => rename_name(name, &self.names.tags),
+            ItemKind::TypeAlias { name, .. } => rename_name(name, &self.names.values),
+            _ => {}
+        }
+        walk_item_mut(self, item);
+    }
+
+    fn visit_declaration_mut(&mut self, declaration: &mut Declaration) {
+        rename_name(&mut declaration.name, &self.names.values);
+        walk_declaration_mut(self, declaration);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        rename_name(&mut function.name, &self.names.values);
+        walk_function_mut(self, function);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        rename_name(&mut param.name, &self.names.values);
+        walk_param_mut(self, param);
+    }
+
+    // field names belong to the struct, so fields are left to the default walk, which renames their types
+
+    fn visit_variant_mut(&mut self, variant: &mut Variant) {
+        rename_name(&mut variant.name, &self.names.values);
+        walk_variant_mut(self, variant);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let ExprKind::Identifier(name) | ExprKind::Call { name, .. } = &mut expr.kind {
+            rename_name(name, &self.names.values);
+        }
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_type_mut(&mut self, typ: &mut Type) {
+        if let Type::Struct(name) | Type::Enum(name) = typ {
+            rename_name(name, &self.names.tags);
+        }
+        walk_type_mut(self, typ);
+    }
+}
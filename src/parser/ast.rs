@@ -1,19 +1,11 @@
 use std::collections::VecDeque;
-use crate::error;
 use crate::lexer::lexer::{SymbolKind, Token};
-use crate::parser::types::{OperatorType, Punctuation, Type};
+use crate::parser::error::ParseError;
+use crate::parser::program::{AssignOp, BinOp, Expr, UnaryOp, UpdateOp};
+use crate::parser::span::Span;
+use crate::parser::types::{Keyword, OperatorType, Punctuation, Type};
 
 
-impl Type {
-    pub fn from_identifier(identifier: &ASTNode) -> Option<Type> {
-        if let ASTNode::Identifier(ident) = identifier {
-            Type::from_str(ident)
-        } else {
-            None
-        }
-    }
-}
-
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum ASTNode {
     Number(i32),
@@ -24,25 +16,109 @@ pub enum ASTNode {
 
     Declaration {
         typ: Type,
-        name: Box<ASTNode>,
+        name: Box<SymbolNode>,
     },
     DeclarationAssignment {
         typ: Type,
-        name: Box<ASTNode>,
-        value: Box<ASTNode>,
+        name: Box<SymbolNode>,
+        value: Box<SymbolNode>,
+    },
+    Declarations {
+        typ: Type, // written before the first name, every later name is declared with it
+        declarations: Vec<SymbolNode>,
+    },
+    ConstDeclaration {
+        declaration: Box<SymbolNode>, // a declaration, with or without a value, or a list of them
     },
     Assign {
-        name: Box<ASTNode>,
-        value: Box<ASTNode>,
+        name: Box<SymbolNode>,
+        value: Box<SymbolNode>,
+    },
+    CompoundAssign {
+        target: Box<SymbolNode>,
+        operation: AssignOp, // `Add` for `+=`, ...
+        value: Box<SymbolNode>,
     },
     BinaryOperation {
-        left: Box<ASTNode>,
-        right: Box<ASTNode>,
-        operation: Box<ASTNode>,
+        left: Box<SymbolNode>,
+        right: Box<SymbolNode>,
+        operation: BinOp,
+    },
+    UnaryOperation {
+        operation: UnaryOp,
+        operand: Box<SymbolNode>,
+    },
+    UpdateOperation {
+        operation: UpdateOp,
+        operand: Box<SymbolNode>,
+        prefix: bool, // `++x` is the new value, `x++` the old one
+    },
+    Conditional {
+        condition: Box<SymbolNode>,
+        if_true: Box<SymbolNode>,
+        if_false: Box<SymbolNode>,
+    },
+    Cast {
+        typ: Type,
+        operand: Box<SymbolNode>,
+    },
+    Function {
+        return_type: Type,
+        name: Box<SymbolNode>,
+        params: Vec<SymbolNode>,
+        body: Vec<SymbolNode>,
+    },
+    Call {
+        name: Box<SymbolNode>,
+        args: Vec<SymbolNode>,
+    },
+    Index {
+        array: Box<SymbolNode>,
+        index: Box<SymbolNode>,
+    },
+    Member {
+        object: Box<SymbolNode>,
+        field: Box<SymbolNode>,
+    },
+    StructDeclaration {
+        name: Box<SymbolNode>,
+        fields: Vec<SymbolNode>,
+    },
+    EnumDeclaration {
+        name: Box<SymbolNode>,
+        variants: Vec<SymbolNode>,
+    },
+    TypeAlias {
+        name: Box<SymbolNode>,
+        typ: Type,
+    },
+    EnumVariant {
+        name: Box<SymbolNode>,
+        value: Option<Box<SymbolNode>>, // one more than the variant before when not given
+    },
+    Import {
+        path: String, // relative to the file that imports it
+    },
+    Public {
+        item: Box<SymbolNode>, // visible to the modules that import this one
     },
+    Return {
+        value: Option<Box<SymbolNode>>,
+    },
+    Switch {
+        value: Box<SymbolNode>,
+        cases: Vec<SymbolNode>,
+    },
+    Case {
+        value: Option<Box<SymbolNode>>, // `None` for `default`
+        body: Vec<SymbolNode>,
+    },
+    Break(),
     Operator(OperatorType),
     Punctuation(Punctuation),
-    Expr(Box<ASTNode>),
+    Keyword(Keyword),
+    Type(Type),
+    Error(), // replaces the input skipped after a syntax error
     Empty(), //
 }
 use std::fmt;
@@ -62,89 +138,544 @@ impl fmt::Display for ASTNode {
             ASTNode::DeclarationAssignment { typ, name, value } => {
                 write!(f, "DeclarationAssignment({:?}, {}, {})", typ, name, value)
             }
+            ASTNode::Declarations { typ, declarations } => {
+                write!(f, "Declarations({:?}, [", typ)?;
+                write_list(f, declarations)?;
+                write!(f, "])")
+            }
+            ASTNode::ConstDeclaration { declaration } => write!(f, "Const({})", declaration),
             ASTNode::Assign { name, value } => write!(f, "Assign({}, {})", name, value),
+            ASTNode::CompoundAssign { target, operation, value } => {
+                write!(f, "CompoundAssign({}, {:?}, {})", target, operation, value)
+            }
             ASTNode::BinaryOperation { left, right, operation } => {
-                write!(f, "BinaryOperation({}, {}, {})", left, operation, right)
+                write!(f, "BinaryOperation({}, {:?}, {})", left, operation, right)
+            }
+            ASTNode::UnaryOperation { operation, operand } => {
+                write!(f, "UnaryOperation({:?}, {})", operation, operand)
+            }
+            ASTNode::UpdateOperation { operation, operand, prefix: true } => {
+                write!(f, "UpdateOperation({:?}, {})", operation, operand)
+            }
+            ASTNode::UpdateOperation { operation, operand, prefix: false } => {
+                write!(f, "UpdateOperation({}, {:?})", operand, operation)
+            }
+            ASTNode::Conditional { condition, if_true, if_false } => {
+                write!(f, "Conditional({}, {}, {})", condition, if_true, if_false)
+            }
+            ASTNode::Cast { typ, operand } => write!(f, "Cast({:?}, {})", typ, operand),
+            ASTNode::Function { return_type, name, params, body } => {
+                write!(f, "Function({:?}, {}, [", return_type, name)?;
+                write_list(f, params)?;
+                write!(f, "], [")?;
+                write_list(f, body)?;
+                write!(f, "])")
+            }
+            ASTNode::Call { name, args } => {
+                write!(f, "Call({}, [", name)?;
+                write_list(f, args)?;
+                write!(f, "])")
+            }
+            ASTNode::Index { array, index } => write!(f, "Index({}, {})", array, index),
+            ASTNode::Member { object, field } => write!(f, "Member({}, {})", object, field),
+            ASTNode::StructDeclaration { name, fields } => {
+                write!(f, "StructDeclaration({}, [", name)?;
+                write_list(f, fields)?;
+                write!(f, "])")
+            }
+            ASTNode::EnumDeclaration { name, variants } => {
+                write!(f, "EnumDeclaration({}, [", name)?;
+                write_list(f, variants)?;
+                write!(f, "])")
             }
+            ASTNode::EnumVariant { name, value: Some(value) } => write!(f, "{} = {}", name, value),
+            ASTNode::EnumVariant { name, value: None } => write!(f, "{}", name),
+            ASTNode::TypeAlias { name, typ } => write!(f, "TypeAlias({}, {:?})", name, typ),
+            ASTNode::Import { path } => write!(f, "Import(\"{}\")", path),
+            ASTNode::Public { item } => write!(f, "Public({})", item),
+            ASTNode::Return { value: Some(value) } => write!(f, "Return({})", value),
+            ASTNode::Return { value: None } => write!(f, "Return"),
+            ASTNode::Switch { value, cases } => {
+                write!(f, "Switch({}, [", value)?;
+                write_list(f, cases)?;
+                write!(f, "])")
+            }
+            ASTNode::Case { value: Some(value), body } => {
+                write!(f, "Case({}, [", value)?;
+                write_list(f, body)?;
+                write!(f, "])")
+            }
+            ASTNode::Case { value: None, body } => {
+                write!(f, "Default([")?;
+                write_list(f, body)?;
+                write!(f, "])")
+            }
+            ASTNode::Break() => write!(f, "Break"),
             ASTNode::Operator(op) => write!(f, "{:?}", op),
             ASTNode::Punctuation(p) => write!(f, "{:?}", p),
-            ASTNode::Expr(expr) => write!(f, "Expr({})", expr),
+            ASTNode::Keyword(k) => write!(f, "{:?}", k),
+            ASTNode::Type(typ) => write!(f, "{:?}", typ),
+            ASTNode::Error() => write!(f, "Error"),
             ASTNode::Empty() => write!(f, "Empty"),
         }
     }
 }
 
+fn write_list(f: &mut fmt::Formatter<'_>, nodes: &[SymbolNode]) -> fmt::Result {
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", node)?;
+    }
+    Ok(())
+}
 
 impl ASTNode {
-    pub fn from_token_value(token: &Token) -> ASTNode {
-        match token.get_kind() {
-            SymbolKind::Number => ASTNode::Number(token.get_value().parse().unwrap()),
-            SymbolKind::Identifier => ASTNode::Identifier(token.get_value()),
-            SymbolKind::String => ASTNode::String(token.get_value()),
-            SymbolKind::Bool => ASTNode::Bool(token.get_value() == "true".to_string()),
-            SymbolKind::Char => ASTNode::Char(token.get_value().chars().next().unwrap()),
-            SymbolKind::Operator => ASTNode::Operator(OperatorType::from_char(token.get_value().chars().next().unwrap()).unwrap()),
-            SymbolKind::Punctuation => ASTNode::Punctuation(Punctuation::from_char(token.get_value().chars().next().unwrap()).unwrap()),
-            _ => ASTNode::Empty(),
-        }
+    pub fn from_token_value(token: &Token) -> Result<ASTNode, ParseError> {
+        let value = token.get_value();
+        let first = value.chars().next();
+        let node = match token.get_kind() {
+            SymbolKind::Number => value.parse().ok().map(ASTNode::Number),
+            SymbolKind::Identifier => Some(ASTNode::Identifier(value.clone())),
+            SymbolKind::String => Some(ASTNode::String(value.clone())),
+            SymbolKind::Bool => Some(ASTNode::Bool(value == "true")),
+            SymbolKind::Char => first.map(ASTNode::Char),
+            SymbolKind::Operator => OperatorType::from_str(&value).map(ASTNode::Operator),
+            SymbolKind::Punctuation => first.and_then(Punctuation::from_char).map(ASTNode::Punctuation),
+            SymbolKind::Keyword => Keyword::from_str(&value).map(ASTNode::Keyword),
+            _ => Some(ASTNode::Empty()),
+        };
+
+        node.ok_or_else(|| {
+            let span = token.get_span();
+            match token.get_kind() {
+                SymbolKind::Number | SymbolKind::Char => ParseError::InvalidLiteral { span, literal: value.clone() },
+                _ => ParseError::UnexpectedToken { span, found: format!("`{}`", value), expected: Vec::new() },
+            }
+        })
     }
     pub fn create_assign(symbols: &mut VecDeque<SymbolNode>, ) -> ASTNode {
-        let name = Box::new(symbols.pop_front().unwrap().get_value());
+        // the target is either a variable name or an element expression such as `a[i]`
+        let name = Box::new(symbols.pop_front().unwrap());
         let _ = symbols.pop_front(); // Pop '='
-        let value = Box::new(symbols.pop_front().unwrap().value);
+        let value = Box::new(symbols.pop_front().unwrap());
 
         ASTNode::Assign {name, value }
     }
 
+    /// `target += value;`
+    pub fn create_compound_assign(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let target = Box::new(symbols.pop_front().unwrap());
+        let operation = Self::get_operator(symbols, AssignOp::from_operator);
+        let value = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::CompoundAssign { target, operation, value }
+    }
+
     pub fn create_binary_op(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
         ASTNode::BinaryOperation {
-            left: Box::new(symbols.pop_front().unwrap().get_value()),
-            operation: Box::new(symbols.pop_front().unwrap().get_value()),
-            right: Box::new(symbols.pop_front().unwrap().get_value()),
+            left: Box::new(symbols.pop_front().unwrap()),
+            operation: Self::get_operator(symbols, BinOp::from_operator),
+            right: Box::new(symbols.pop_front().unwrap()),
+        }
+    }
+
+    /// `*p` or `&x`
+    pub fn create_unary_op(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        ASTNode::UnaryOperation {
+            operation: Self::get_operator(symbols, UnaryOp::from_operator),
+            operand: Box::new(symbols.pop_front().unwrap()),
         }
     }
 
-    pub fn create_expr(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
-        ASTNode::Expr(Box::new(symbols.pop_front().unwrap().get_value()))
+    /// `++x` or `--x`
+    pub fn create_prefix_update(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        ASTNode::UpdateOperation {
+            operation: Self::get_operator(symbols, UpdateOp::from_operator),
+            operand: Box::new(symbols.pop_front().unwrap()),
+            prefix: true,
+        }
     }
 
-    pub fn create_declaration(symbols: &mut VecDeque<SymbolNode>, line: u32) -> ASTNode {
-        let typ = Self::get_type(symbols, line);
-        let name = Box::new(symbols.pop_front().unwrap().get_value());
+    /// `x++` or `x--`
+    pub fn create_postfix_update(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let operand = Box::new(symbols.pop_front().unwrap());
+        ASTNode::UpdateOperation { operation: Self::get_operator(symbols, UpdateOp::from_operator), operand, prefix: false }
+    }
+
+    /// `condition ? if_true : if_false`
+    pub fn create_conditional(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let condition = Box::new(symbols.pop_front().unwrap());
+        let _ = symbols.pop_front(); // Pop '?'
+        let if_true = Box::new(symbols.pop_front().unwrap());
+        let _ = symbols.pop_front(); // Pop ':'
+        let if_false = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Conditional { condition, if_true, if_false }
+    }
+
+    /// `( type ) operand`
+    pub fn create_cast(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop '('
+        let typ = Self::get_type(symbols);
+        let _ = symbols.pop_front(); // Pop ')'
+        let operand = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Cast { typ, operand }
+    }
+
+    pub fn create_pointer_type(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let typ = Self::get_type(symbols);
+        ASTNode::Type(Type::Pointer(Box::new(typ)))
+    }
+
+    /// `foo x`, where `foo` does not name a type
+    pub fn create_unknown_type_declaration(symbols: &mut VecDeque<SymbolNode>, span: Span) -> Result<ASTNode, ParseError> {
+        let token = symbols.pop_front().unwrap().get_value();
+        Err(ParseError::UnknownType { span, name: token.as_identifier().unwrap_or_default().to_string() })
+    }
+
+    pub fn create_declaration(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let typ = Self::get_type(symbols);
+        let name = Box::new(symbols.pop_front().unwrap());
 
         ASTNode::Declaration {typ, name,}
     }
 
-    pub fn create_declaration_assignment(symbols: &mut VecDeque<SymbolNode>, line: u32) -> ASTNode {
-        let typ = Self::get_type(symbols, line);
-        let name = Box::new(symbols.pop_front().unwrap().get_value());
+    pub fn create_declaration_assignment(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let Some((typ, name)) = symbols.pop_front().unwrap().get_value().into_declaration() else {
+            unreachable!("declaration assignment must start with a declaration head");
+        };
 
         let _ = symbols.pop_front(); // Pop '='
-        let value = Box::new(symbols.pop_front().unwrap().value);
+        let value = Box::new(symbols.pop_front().unwrap());
 
         ASTNode::DeclarationAssignment {typ, name, value}
     }
 
-    fn get_type(symbols: &mut VecDeque<SymbolNode>, line_number: u32) -> Type
-    {
-        let token = symbols.pop_front().unwrap().get_value();
-        let typ = Type::from_identifier(&token);
-        if typ.is_none() {
-            if let ASTNode::Identifier(wrong_type) = token{
-                error!("Error at line: {} Unknown type {:?}", line_number, wrong_type);
-                std::process::exit(1);
+    /// `int a,` or `int a = 1,` opens a list, the later names get the type without the array suffixes
+    pub fn create_declaration_list(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let head = symbols.pop_front().unwrap();
+        let span = head.span;
+        let Some((typ, name)) = head.get_value().into_declaration() else {
+            unreachable!("declaration list must start with a declaration head");
+        };
+        let base = typ.clone().without_dimensions();
+        let declaration = match symbols.pop_front().unwrap().get_value() {
+            ASTNode::Operator(OperatorType::Equal) => {
+                let value = symbols.pop_front().unwrap();
+                let span = span.to(value.span);
+                let declaration = ASTNode::DeclarationAssignment { typ, name, value: Box::new(value) };
+                SymbolNode::new(SymbolKind::DeclarationAssignment, declaration, span)
             }
+            _ => SymbolNode::new(SymbolKind::Declaration, ASTNode::Declaration { typ, name }, span),
+        };
+
+        ASTNode::Declarations { typ: base, declarations: vec![declaration] }
+    }
+
+    /// Declares the name following the (still open) list, its suffixes and value may follow
+    pub fn add_declarator(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut list = symbols.pop_front().unwrap().get_value();
+        let name = symbols.pop_front().unwrap();
+        if let ASTNode::Declarations { typ, declarations } = &mut list {
+            let span = name.span;
+            let declaration = ASTNode::Declaration { typ: typ.clone(), name: Box::new(name) };
+            declarations.push(SymbolNode::new(SymbolKind::Declaration, declaration, span));
         }
-        typ.unwrap()
+        list
     }
 
-    pub fn inferred_type(&self) -> Option<Type> {
-        match self {
-            ASTNode::Number(_) => Some(Type::Integer),
-            ASTNode::String(_) => Some(Type::String),
-            ASTNode::Bool(_) => Some(Type::Bool),
-            ASTNode::Char(_) => Some(Type::Char),
+    /// Adds a `[length]` suffix to the last name of the list
+    pub fn add_declarator_dimension(symbols: &mut VecDeque<SymbolNode>, _: Span) -> Result<ASTNode, ParseError> {
+        let mut list = symbols.pop_front().unwrap().get_value();
+        let _ = symbols.pop_front(); // Pop '['
+        let length = symbols.pop_front().unwrap();
+        let close = symbols.pop_front().unwrap();
+        if let ASTNode::Declarations { declarations, .. } = &mut list
+            && let Some(SymbolNode { kind, value: ASTNode::Declaration { typ, name }, span }) = declarations.pop()
+        {
+            let length = Self::array_length(length, &name)?;
+            let declaration = ASTNode::Declaration { typ: typ.with_dimension(length), name };
+            declarations.push(SymbolNode::new(kind, declaration, span.to(close.span)));
+        }
+        Ok(list)
+    }
+
+    /// Ends the last name of the list at its `,` or `;`, giving it the value after `=` if there is one
+    pub fn finish_declarator(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut list = symbols.pop_front().unwrap().get_value();
+        let is_assignment = symbols.pop_front().unwrap().get_value().as_operator() == Some(&OperatorType::Equal);
+        if is_assignment
+            && let ASTNode::Declarations { declarations, .. } = &mut list
+            && let Some(SymbolNode { value: ASTNode::Declaration { typ, name }, span, .. }) = declarations.pop()
+        {
+            let value = symbols.pop_front().unwrap();
+            let span = span.to(value.span);
+            let declaration = ASTNode::DeclarationAssignment { typ, name, value: Box::new(value) };
+            declarations.push(SymbolNode::new(SymbolKind::DeclarationAssignment, declaration, span));
+        }
+        list
+    }
+
+    /// `const` before a declaration
+    pub fn create_const_declaration(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'const'
+        let declaration = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::ConstDeclaration { declaration }
+    }
+
+    pub fn create_function_header(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let Some((return_type, name)) = symbols.pop_front().unwrap().get_value().into_declaration() else {
+            unreachable!("function header must start with a declaration head");
+        };
+
+        ASTNode::Function { return_type, name, params: Vec::new(), body: Vec::new() }
+    }
+
+    /// Appends the parameter following the header (`header param ,` or `header param )`)
+    pub fn add_function_param(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut function = symbols.pop_front().unwrap().get_value();
+        let param = symbols.pop_front().unwrap();
+        if let ASTNode::Function { params, .. } = &mut function {
+            params.push(param);
+        }
+        function
+    }
+
+    /// Appends the statement following the (still open) function body
+    pub fn add_function_statement(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut function = symbols.pop_front().unwrap().get_value();
+        let statement = symbols.pop_front().unwrap();
+        if let ASTNode::Function { body, .. } = &mut function {
+            body.push(statement);
+        }
+        function
+    }
+
+    /// Adds a `[length]` suffix to the declaration head before it
+    pub fn create_array_declaration(symbols: &mut VecDeque<SymbolNode>, _: Span) -> Result<ASTNode, ParseError> {
+        let Some((typ, name)) = symbols.pop_front().unwrap().get_value().into_declaration() else {
+            unreachable!("array size must follow a declaration head");
+        };
+        let _ = symbols.pop_front(); // Pop '['
+        let length = symbols.pop_front().unwrap();
+        let length = Self::array_length(length, &name)?;
+
+        Ok(ASTNode::Declaration { typ: typ.with_dimension(length), name })
+    }
+
+    /// The length of an array, an error at the length written when it is not a positive constant
+    fn array_length(length: SymbolNode, name: &SymbolNode) -> Result<u32, ParseError> {
+        let span = length.span;
+        match Expr::from(length).evaluate_constant() {
+            Some(length) if length > 0 => Ok(length as u32),
+            _ => Err(ParseError::InvalidArraySize { span, name: name.to_string() }),
+        }
+    }
+
+    pub fn create_index(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let array = Box::new(symbols.pop_front().unwrap());
+        let _ = symbols.pop_front(); // Pop '['
+        let index = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Index { array, index }
+    }
+
+    pub fn create_member(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let object = Box::new(symbols.pop_front().unwrap());
+        let _ = symbols.pop_front(); // Pop '.'
+        let field = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Member { object, field }
+    }
+
+    pub fn create_struct_type(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'struct'
+        let name = symbols.pop_front().unwrap().get_value();
+
+        ASTNode::Type(Type::Struct(name.as_identifier().unwrap_or_default().to_string()))
+    }
+
+    pub fn create_enum_type(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'enum'
+        let name = symbols.pop_front().unwrap().get_value();
+
+        ASTNode::Type(Type::Enum(name.as_identifier().unwrap_or_default().to_string()))
+    }
+
+    pub fn create_enum_header(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'enum'
+        let name = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::EnumDeclaration { name, variants: Vec::new() }
+    }
+
+    /// `Red` or `Green = 5`
+    pub fn create_enum_variant(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let name = Box::new(symbols.pop_front().unwrap());
+        let _ = symbols.pop_front(); // Pop '='
+        let value = symbols.pop_front().map(Box::new);
+
+        ASTNode::EnumVariant { name, value }
+    }
+
+    /// Appends the variant following the (still open) enum body
+    pub fn add_enum_variant(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut declaration = symbols.pop_front().unwrap().get_value();
+        let variant = symbols.pop_front().unwrap();
+        if let ASTNode::EnumDeclaration { variants, .. } = &mut declaration {
+            variants.push(variant);
+        }
+        declaration
+    }
+
+    /// `typedef int Id;`, the name is declared like a variable of the aliased type
+    pub fn create_type_alias(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'typedef'
+        let Some((typ, name)) = symbols.pop_front().unwrap().get_value().into_declaration() else {
+            unreachable!("typedef must be followed by a declaration head");
+        };
+
+        ASTNode::TypeAlias { name, typ }
+    }
+
+    pub fn create_import(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'import'
+        let ASTNode::String(path) = symbols.pop_front().unwrap().get_value() else {
+            unreachable!("import must be followed by a string");
+        };
+
+        ASTNode::Import { path }
+    }
+
+    pub fn create_public(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'pub'
+        let item = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Public { item }
+    }
+
+    pub fn create_struct_header(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'struct'
+        let name = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::StructDeclaration { name, fields: Vec::new() }
+    }
+
+    /// Appends the field declaration following the (still open) struct body, `int x, y;` as two fields
+    pub fn add_struct_field(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut declaration = symbols.pop_front().unwrap().get_value();
+        let field = symbols.pop_front().unwrap();
+        if let ASTNode::StructDeclaration { fields, .. } = &mut declaration {
+            match field.value {
+                ASTNode::Declarations { declarations, .. } => fields.extend(declarations),
+                value => fields.push(SymbolNode { value, ..field }),
+            }
+        }
+        declaration
+    }
+
+    pub fn create_call_header(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let name = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Call { name, args: Vec::new() }
+    }
+
+    /// Appends the argument following the header (`header arg ,` or `header arg )`)
+    pub fn add_call_arg(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut call = symbols.pop_front().unwrap().get_value();
+        let arg = symbols.pop_front().unwrap();
+        if let ASTNode::Call { args, .. } = &mut call {
+            args.push(arg);
+        }
+        call
+    }
+
+    pub fn create_return(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'return'
+        let value = symbols.pop_front().filter(|s| s.kind == SymbolKind::Expr);
+
+        ASTNode::Return { value: value.map(Box::new) }
+    }
+
+    /// `switch ( value ) {`
+    pub fn create_switch_header(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front(); // Pop 'switch'
+        let _ = symbols.pop_front(); // Pop '('
+        let value = Box::new(symbols.pop_front().unwrap());
+
+        ASTNode::Switch { value, cases: Vec::new() }
+    }
+
+    /// `case value :` or `default :`, the statements up to the next label are added to it
+    pub fn create_case_label(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let label = symbols.pop_front().unwrap().get_value();
+        let value = match label.as_keyword() {
+            Some(Keyword::Case) => Some(Box::new(symbols.pop_front().unwrap())),
             _ => None,
+        };
+
+        ASTNode::Case { value, body: Vec::new() }
+    }
+
+    /// Appends the case label following the (still open) switch body
+    pub fn add_switch_case(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut switch = symbols.pop_front().unwrap().get_value();
+        let case = symbols.pop_front().unwrap();
+        if let ASTNode::Switch { cases, .. } = &mut switch {
+            cases.push(case);
+        }
+        switch
+    }
+
+    /// Appends the statement following the (still open) switch body to its last case, which then ends with it
+    pub fn add_switch_statement(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let mut switch = symbols.pop_front().unwrap().get_value();
+        let statement = symbols.pop_front().unwrap();
+        if let ASTNode::Switch { cases, .. } = &mut switch
+            && let Some(SymbolNode { value: ASTNode::Case { body, .. }, span, .. }) = cases.last_mut()
+        {
+            *span = span.to(statement.span);
+            body.push(statement);
+        }
+        switch
+    }
+
+    /// Keeps the symbol between the surrounding punctuation, e.g. `( expr )`
+    pub fn take_second(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        let _ = symbols.pop_front();
+        symbols.pop_front().unwrap().get_value()
+    }
+
+    /// Keeps the first matched symbol and drops the punctuation around it
+    pub fn take_first(symbols: &mut VecDeque<SymbolNode>) -> ASTNode {
+        symbols.pop_front().unwrap().get_value()
+    }
+
+    fn get_type(symbols: &mut VecDeque<SymbolNode>) -> Type
+    {
+        match symbols.pop_front().unwrap().get_value() {
+            ASTNode::Type(typ) => typ,
+            other => unreachable!("expected a type name, got {:?}", other),
+        }
+    }
+
+    /// The operator of the rule, as the operation the rule gives it
+    fn get_operator<T>(symbols: &mut VecDeque<SymbolNode>, operation: fn(OperatorType) -> Option<T>) -> T {
+        match symbols.pop_front().unwrap().get_value() {
+            ASTNode::Operator(operator) => operation(operator).unwrap_or_else(|| unreachable!("unexpected operator {:?}", operator)),
+            other => unreachable!("expected an operator, got {:?}", other),
+        }
+    }
+
+    fn into_declaration(self) -> Option<(Type, Box<SymbolNode>)> {
+        if let ASTNode::Declaration { typ, name } = self {
+            Some((typ, name))
+        } else {
+            None
         }
     }
 
@@ -184,7 +715,7 @@ impl ASTNode {
         }
     }
 
-    pub fn as_declaration(&self) -> Option<(&Type, &ASTNode)> {
+    pub fn as_declaration(&self) -> Option<(&Type, &SymbolNode)> {
         if let ASTNode::Declaration { typ, name } = self {
             Some((typ, name.as_ref()))
         } else {
@@ -192,30 +723,6 @@ impl ASTNode {
         }
     }
 
-    pub fn as_declaration_assignment(&self) -> Option<(&Type, &ASTNode, &ASTNode)> {
-        if let ASTNode::DeclarationAssignment { typ, name, value } = self {
-            Some((typ, name.as_ref(), value.as_ref()))
-        } else {
-            None
-        }
-    }
-
-    pub fn as_assign(&self) -> Option<(&ASTNode, &ASTNode)> {
-        if let ASTNode::Assign { name, value } = self {
-            Some((name.as_ref(), value.as_ref()))
-        } else {
-            None
-        }
-    }
-
-    pub fn as_binary_operation(&self) -> Option<(&ASTNode, &ASTNode, &ASTNode)> {
-        if let ASTNode::BinaryOperation { left, right, operation } = self {
-            Some((left.as_ref(), right.as_ref(), operation.as_ref()))
-        } else {
-            None
-        }
-    }
-
     pub fn as_operator(&self) -> Option<&OperatorType> {
         if let ASTNode::Operator(op) = self {
             Some(op)
@@ -224,25 +731,9 @@ impl ASTNode {
         }
     }
 
-    pub fn as_punctuation(&self) -> Option<&Punctuation> {
-        if let ASTNode::Punctuation(p) = self {
-            Some(p)
-        } else {
-            None
-        }
-    }
-
-    pub fn as_expr(&self) -> Option<&ASTNode> {
-        if let ASTNode::Expr(inner) = self {
-            Some(inner.as_ref())
-        } else {
-            None
-        }
-    }
-
-    pub fn as_empty(&self) -> Option<()> {
-        if let ASTNode::Empty() = self {
-            Some(())
+    pub fn as_keyword(&self) -> Option<&Keyword> {
+        if let ASTNode::Keyword(k) = self {
+            Some(k)
         } else {
             None
         }
@@ -250,17 +741,25 @@ impl ASTNode {
 }
 
 
-#[derive(Debug, PartialEq, Eq)]
+/// A symbol of the parser stack, and a node of the tree: the children of a node are the symbols it was made of
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct SymbolNode {
     pub kind: SymbolKind,
     pub value: ASTNode,
+    pub span: Span,
 }
 impl SymbolNode {
 
-    pub fn new(kind: SymbolKind, value: ASTNode) -> SymbolNode {
-        SymbolNode { kind, value, }
+    pub fn new(kind: SymbolKind, value: ASTNode, span: Span) -> SymbolNode {
+        SymbolNode { kind, value, span }
     }
     pub fn get_value(self) -> ASTNode {
         self.value
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for SymbolNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
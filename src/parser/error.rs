@@ -0,0 +1,61 @@
+use std::fmt;
+
+use super::span::Span;
+
+/// Why the input is not a valid program, every variant carries the span it was found at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token the grammar does not allow where it was found
+    UnexpectedToken { span: Span, found: String, expected: Vec<String> },
+    /// The input ended before the program was complete
+    UnexpectedEof { span: Span, expected: Vec<String> },
+    /// `foo x;` where `foo` does not name a type
+    UnknownType { span: Span, name: String },
+    /// A number or character that has no value, e.g. one out of the `int` range
+    InvalidLiteral { span: Span, literal: String },
+    /// `int a[n];` where `n` is not a positive constant
+    InvalidArraySize { span: Span, name: String },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::UnknownType { span, .. }
+            | ParseError::InvalidLiteral { span, .. }
+            | ParseError::InvalidArraySize { span, .. } => *span,
+        }
+    }
+
+    pub fn line(&self) -> u32 {
+        self.span().line
+    }
+}
+
+fn one_of(expected: &[String]) -> String {
+    match expected.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => "nothing".to_string(),
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Error at {}: ", self.span())?;
+        match self {
+            ParseError::UnexpectedToken { found, expected, .. } => {
+                write!(f, "unexpected {}, expected {}", found, one_of(expected))
+            }
+            ParseError::UnexpectedEof { expected, .. } => {
+                write!(f, "unexpected end of input, expected {}", one_of(expected))
+            }
+            ParseError::UnknownType { name, .. } => write!(f, "Unknown type {:?}", name),
+            ParseError::InvalidLiteral { literal, .. } => write!(f, "invalid literal {}", literal),
+            ParseError::InvalidArraySize { name, .. } => {
+                write!(f, "array {} must have a constant positive size", name)
+            }
+        }
+    }
+}
@@ -1,4 +1,12 @@
+pub mod error;
 pub mod parser;
-mod rules;
+mod pratt;
+pub mod printer;
+pub mod program;
+pub mod rules;
+pub mod span;
+pub mod table;
+pub mod trace;
 pub mod types;
+pub mod visit;
 pub mod ast;
\ No newline at end of file
@@ -1,94 +1,289 @@
 use crate::lexer::lexer::{Lexer, SymbolKind};
-use super::rules::RULES;
+use super::error::ParseError;
+use super::rules::{Pattern, Rule, RULES, START};
+use super::table::{ParseAction, TABLE};
+use super::trace::{Trace, TraceLevel};
 use std::collections::VecDeque;
-use crate::helper::visualize_ast::visualize_ast;
+use std::io::Write;
 use super::ast::{ASTNode, SymbolNode};
+use super::program::Program;
+use super::span::Span;
+use super::types::{Keyword, Type, TypeTable};
 
-pub struct Parser {
+/// Tokens that must be shifted after a recovery before another syntax error is reported,
+/// so that one mistake does not cascade into several diagnostics
+const RECOVERY_TOKENS: usize = 3;
+
+pub struct Parser<'a> {
     lexer: Lexer,
-    current_line: u32,
+    current_span: Span, // of the last symbol read
+    lookahead: VecDeque<(SymbolNode, String)>, // each with its source text, to tell what was found
+    shifted_since_error: usize,
+    errors: Vec<ParseError>,
+    trace: Trace<'a>,
+    pratt_expressions: bool,
+    types: TypeTable, // identifiers that are read as a type name
+    after_tag: bool,  // the last symbol read was `struct` or `enum`, the name of which is never a type
 }
 
-impl Parser {
-    pub fn new(file_name: &str) -> Parser {
+impl<'a> Parser<'a> {
+    pub fn new(file_name: &str) -> Parser<'a> {
         Parser {
             lexer: Lexer::new(file_name),
-            current_line: 1,
+            current_span: Span::at(1, 1),
+            lookahead: VecDeque::new(),
+            shifted_since_error: RECOVERY_TOKENS,
+            errors: Vec::new(),
+            trace: Trace::off(),
+            pratt_expressions: false,
+            types: TypeTable::new(),
+            after_tag: false,
+        }
+    }
+
+    /// Parses whole expressions by precedence climbing instead of the table, see `pratt.rs`.
+    /// The program is the same, only the trace shows fewer and more compact symbols
+    pub fn enable_pratt_expressions(&mut self) {
+        self.pratt_expressions = true;
+    }
+
+    /// Describes the parse in the sink while parsing, see `TraceLevel` for how much is written
+    pub fn enable_trace(&mut self, level: TraceLevel, sink: Box<dyn Write + 'a>) {
+        self.trace = Trace::new(level, sink);
+    }
+
+    /// Reads `name` as `typ` as if a typedef came before the input, false when it already names a type
+    pub fn add_type(&mut self, name: &str, typ: Type) -> bool {
+        self.types.add_alias(name, typ)
+    }
+
+    /// Parses the input into its top-level items, or every error found in it
+    pub fn parse(&mut self) -> Result<Program, Vec<ParseError>> {
+        let (items, errors) = self.parse_with_diagnostics();
+        if errors.is_empty() {
+            Ok(items)
+        } else {
+            Err(errors)
         }
     }
 
-    /// Parses the input and returns the list of top-level AST symbols
-    pub fn parse(&mut self) -> Vec<SymbolNode> {
+    /// Parses the whole input, recovering from errors: the top-level items are returned
+    /// with `Error` nodes in place of the skipped input, together with every error found (by line)
+    pub fn parse_with_diagnostics(&mut self) -> (Program, Vec<ParseError>) {
+        let mut states: Vec<usize> = vec![0];
         let mut symbols: Vec<SymbolNode> = Vec::new();
+        let mut items: Vec<SymbolNode> = Vec::new();
 
-        while let Some(token) = self.lexer.get_next_token() {
-            self.current_line = token.get_line_number();
+        self.errors.clear();
+        loop {
+            let state = *states.last().unwrap();
 
-            if token.get_kind() == SymbolKind::Whitespace {
+            if let Some(target) = TABLE.goto(state, SymbolKind::Expr).filter(|_| self.pratt_expressions && self.starts_expression()) {
+                match self.parse_expression() {
+                    Ok(expr) => {
+                        symbols.push(SymbolNode { kind: SymbolKind::Expr, ..expr });
+                        states.push(target);
+                        self.trace.shift(self.current_span.line, symbols.last().unwrap(), &symbols);
+                    }
+                    Err(error) => {
+                        if self.shifted_since_error >= RECOVERY_TOKENS {
+                            self.errors.push(error);
+                        }
+                        if !self.recover(&mut states, &mut symbols, &mut items) {
+                            break;
+                        }
+                    }
+                }
                 continue;
             }
 
-            symbols.push(SymbolNode {
-                kind: token.get_kind(),
-                value: ASTNode::from_token_value(&token),
-            });
+            let next = self.peek(0).map(Pattern::of);
+            match TABLE.action(state, next) {
+                Some(ParseAction::Shift(target)) => {
+                    let symbol = self.advance().unwrap();
+                    symbols.push(symbol);
+                    states.push(target);
+                    self.trace.shift(self.current_span.line, symbols.last().unwrap(), &symbols);
+                }
+                Some(ParseAction::Reduce(rule)) => {
+                    // input the grammar allows but the action rejects is skipped like a syntax error
+                    if let Err(error) = self.reduce(&RULES[rule], &mut states, &mut symbols, &mut items) {
+                        self.errors.push(error);
+                        if !self.recover(&mut states, &mut symbols, &mut items) {
+                            break;
+                        }
+                    }
+                }
+                // the input is a whole program, its items were handed out as they were reduced
+                Some(ParseAction::Accept) => break,
+                None => {
+                    if self.shifted_since_error >= RECOVERY_TOKENS {
+                        let error = self.syntax_error(&states);
+                        self.errors.push(error);
+                    }
+                    if !self.recover(&mut states, &mut symbols, &mut items) {
+                        break;
+                    }
+                }
+            }
+        }
 
-            self.try_reduce(&mut symbols);
+        // literals are checked when read, ahead of the tokens before them
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|error| (error.line(), error.span().column));
+        (Program::from_symbols(items), errors)
+    }
+
+    /// The symbol `n` places after the next one, read from the lexer when needed
+    pub(super) fn peek(&mut self, n: usize) -> Option<&SymbolNode> {
+        while self.lookahead.len() <= n {
+            let symbol = self.next_symbol()?;
+            self.lookahead.push_back(symbol);
         }
+        self.lookahead.get(n).map(|(symbol, _)| symbol)
+    }
+
+    /// Consumes the next symbol, which counts as shifted for the error recovery
+    pub(super) fn advance(&mut self) -> Option<SymbolNode> {
+        self.peek(0)?;
+        let (symbol, _) = self.lookahead.pop_front()?;
+        self.current_span = symbol.span;
+        self.shifted_since_error += 1;
+        Some(symbol)
+    }
+
+    /// Reads the next non-whitespace token as a symbol, spanning the token, with the token's text
+    fn next_symbol(&mut self) -> Option<(SymbolNode, String)> {
+        while let Some(token) = self.lexer.get_next_token() {
+            if token.get_kind() == SymbolKind::Whitespace {
+                continue;
+            }
 
-        // Return the completed AST/symbol list after parsing
-        symbols
+            // an invalid literal is reported and parsed on as an `Error` of its kind
+            let value = ASTNode::from_token_value(&token).unwrap_or_else(|error| {
+                self.errors.push(error);
+                ASTNode::Error()
+            });
+            let typ = value.as_identifier().filter(|_| !self.after_tag).and_then(|name| self.types.get(name));
+            let symbol = match typ {
+                Some(typ) => SymbolNode::new(SymbolKind::TypeIdentifier, ASTNode::Type(typ.clone()), token.get_span()),
+                None => SymbolNode::new(token.get_kind(), value, token.get_span()),
+            };
+            self.after_tag = Self::is_tag(&symbol);
+            return Some((symbol, token.get_text()));
+        }
+        None
     }
 
-    fn try_reduce(&mut self, symbols: &mut Vec<SymbolNode>) {
-        let Some(rule_list) = RULES.get(&symbols[symbols.len() - 1].kind) else {
-            return;
+    /// Replaces the matched symbols by the rule's result, spanning them all; on an error they are only dropped
+    fn reduce(&mut self, rule: &Rule, states: &mut Vec<usize>, symbols: &mut Vec<SymbolNode>, items: &mut Vec<SymbolNode>) -> Result<(), ParseError> {
+        let mut matched: VecDeque<SymbolNode> = symbols.drain(symbols.len() - rule.pattern.len()..).collect();
+        states.truncate(states.len() - rule.pattern.len());
+        let span = match (matched.front(), matched.back()) {
+            (Some(first), Some(last)) => first.span.to(last.span),
+            _ => Span::default(), // the empty program, before anything is read
         };
 
-        for rule in rule_list {
-            if self.check_symbols_in_rule(symbols, rule) {
-                self.try_reduce(symbols);
+        // top-level items are handed out as they are reduced instead of being nested in the program
+        if rule.result == START && matched.len() > 1 {
+            items.push(matched.remove(1).unwrap());
+        }
+
+        let node = (rule.action)(&mut matched, span)?;
+        if let ASTNode::TypeAlias { name, typ } = &node {
+            self.define_type(name.value.as_identifier().unwrap_or_default(), typ);
+        }
+        let state = *states.last().unwrap();
+        states.push(TABLE.goto(state, rule.result).expect("no transition for a reduced rule"));
+        symbols.push(SymbolNode::new(rule.result, node, span));
+        self.trace.reduction(self.current_span.line, rule, symbols);
+        Ok(())
+    }
+
+    /// Reads `name` as a type from now on, also in the symbols that were already read ahead
+    fn define_type(&mut self, name: &str, typ: &Type) {
+        self.types.add_alias(name, typ.clone());
+        let mut after_tag = false;
+        for (symbol, _) in self.lookahead.iter_mut() {
+            if !after_tag && symbol.value.as_identifier() == Some(name) {
+                *symbol = SymbolNode::new(SymbolKind::TypeIdentifier, ASTNode::Type(typ.clone()), symbol.span);
             }
+            after_tag = Self::is_tag(symbol);
         }
     }
 
-    fn check_symbols_in_rule(
-        &mut self,
-        symbols: &mut Vec<SymbolNode>,
-        rule: &(SymbolKind, Vec<SymbolKind>),
-    ) -> bool {
-        for i in 1..rule.1.len() {
-            if symbols.len().checked_sub(1 + i).is_none() {
-                return false;
+    fn is_tag(symbol: &SymbolNode) -> bool {
+        matches!(symbol.value.as_keyword(), Some(Keyword::Struct | Keyword::Enum))
+    }
+
+    /// Panic mode: drops the unfinished symbols up to a state that accepts an `Error`, shifts one,
+    /// then skips tokens until one can follow it (a ';' or '}'). False when the input ends first
+    fn recover(&mut self, states: &mut Vec<usize>, symbols: &mut Vec<SymbolNode>, items: &mut Vec<SymbolNode>) -> bool {
+        let error = Some(Pattern::Kind(SymbolKind::Error));
+        // the error stands at the token it was found at, or at the last symbol when the input ended
+        let span = match self.peek(0) {
+            Some(symbol) => symbol.span,
+            None => symbols.last().map(|symbol| symbol.span).unwrap_or_default(),
+        };
+        loop {
+            let state = *states.last().unwrap();
+            if let Some(ParseAction::Shift(target)) = TABLE.action(state, error) {
+                symbols.push(SymbolNode::new(SymbolKind::Error, ASTNode::Error(), span));
+                states.push(target);
+                break;
             }
+            if states.len() > 1 {
+                states.pop();
+                symbols.pop();
+                continue;
+            }
+            // nothing was parsed yet: the program is started as if the input ended, so that it can take the error
+            match TABLE.action(state, None) {
+                Some(ParseAction::Reduce(rule)) => {
+                    if self.reduce(&RULES[rule], states, symbols, items).is_err() {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
 
-            if symbols[symbols.len() - 1 - i].kind != rule.1[rule.1.len() - 1 - i] {
+        let state = *states.last().unwrap();
+        loop {
+            let next = self.peek(0).map(Pattern::of);
+            if TABLE.action(state, next).is_some() {
+                self.shifted_since_error = 0;
+                return true;
+            }
+            if self.advance().is_none() {
                 return false;
             }
         }
-
-        self.create_ast_node(symbols, rule);
-        visualize_ast(symbols);
-        true
     }
 
-    fn create_ast_node(&mut self, symbols: &mut Vec<SymbolNode>, rule: &(SymbolKind, Vec<SymbolKind>)) {
-        let mut matched: VecDeque<SymbolNode> = VecDeque::new();
-        for _ in 0..rule.1.len() {
-            matched.push_front(symbols.pop().unwrap());
-        }
+    /// Describes the token the table has no action for, with the terminals that were expected instead
+    fn syntax_error(&mut self, states: &[usize]) -> ParseError {
+        let expected = TABLE
+            .expected(states)
+            .iter()
+            .map(|terminal| terminal.map_or("end of input".to_string(), |t| t.to_string()))
+            .collect();
+        self.unexpected(expected)
+    }
 
-        let node = match rule.0 {
-            SymbolKind::Assign => ASTNode::create_assign(&mut matched),
-            SymbolKind::Expr => ASTNode::create_expr(&mut matched),
-            SymbolKind::BinaryOperation => ASTNode::create_binary_op(&mut matched),
-            SymbolKind::Declaration => ASTNode::create_declaration(&mut matched, self.current_line),
-            SymbolKind::DeclarationAssignment => {
-                ASTNode::create_declaration_assignment(&mut matched, self.current_line)
+    /// Reports the next symbol (or the end of input) where one of `expected` should have been
+    pub(super) fn unexpected(&mut self, expected: Vec<String>) -> ParseError {
+        let current_span = self.current_span;
+        self.peek(0);
+        match self.lookahead.front() {
+            Some((symbol, text)) => {
+                let found = match Pattern::of(symbol) {
+                    Pattern::Kind(_) => format!("`{}`", text),
+                    terminal => terminal.to_string(),
+                };
+                ParseError::UnexpectedToken { span: symbol.span, found, expected }
             }
-            _ => return,
-        };
-
-        symbols.push(SymbolNode::new(rule.0, node));
+            None => ParseError::UnexpectedEof { span: current_span, expected },
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,235 @@
+use crate::lexer::lexer::SymbolKind;
+use super::ast::{ASTNode, SymbolNode};
+use super::error::ParseError;
+use super::parser::Parser;
+use super::program::{BinOp, UnaryOp, UpdateOp};
+use super::rules::{
+    Pattern, BINARY_OPERATORS, CONDITIONAL_PRECEDENCE, LITERALS, UNARY_OPERATORS, UNARY_PRECEDENCE, UPDATE_OPERATORS,
+};
+use super::span::Span;
+use super::types::{Keyword, OperatorType, Punctuation, Type};
+
+/*
+structure:
+Precedence climbing over the parser's lookahead, used in place of the table for whole expressions.
+The operators, their precedences and the postfix forms are the ones of the grammar in `rules.rs`,
+so both give the same tree. Every node spans
+its first token to its last, parentheses included like the table's `( expr )`.
+ */
+impl Parser<'_> {
+    /// Whether the next tokens begin an expression, `foo x` is left to the table as a declaration
+    pub(super) fn starts_expression(&mut self) -> bool {
+        match self.peek_pattern(0) {
+            Some(Pattern::Kind(SymbolKind::Identifier)) => self.peek_pattern(1) != Some(Pattern::Kind(SymbolKind::Identifier)),
+            Some(pattern) => Self::expression_starts().contains(&pattern),
+            None => false,
+        }
+    }
+
+    /// Parses the longest expression at the lookahead
+    pub(super) fn parse_expression(&mut self) -> Result<SymbolNode, ParseError> {
+        self.parse_binary(0)
+    }
+
+    /// An operand followed by its postfix forms and by binary operators of at least `min` precedence
+    fn parse_binary(&mut self, min: u8) -> Result<SymbolNode, ParseError> {
+        let mut left = self.parse_prefix()?;
+        loop {
+            left = match self.peek_pattern(0) {
+                Some(Pattern::Punctuation(Punctuation::OpenBracket)) => {
+                    self.advance();
+                    let index = self.parse_binary(0)?;
+                    let close = self.expect(Punctuation::CloseBracket)?;
+                    let span = left.span.to(close);
+                    SymbolNode::new(SymbolKind::Index, ASTNode::Index { array: Box::new(left), index: Box::new(index) }, span)
+                }
+                Some(Pattern::Punctuation(Punctuation::Dot)) => {
+                    self.advance();
+                    if self.peek_pattern(0) != Some(Pattern::Kind(SymbolKind::Identifier)) {
+                        return Err(self.unexpected(vec![Pattern::Kind(SymbolKind::Identifier).to_string()]));
+                    }
+                    let field = self.advance().unwrap();
+                    let span = left.span.to(field.span);
+                    SymbolNode::new(SymbolKind::Member, ASTNode::Member { object: Box::new(left), field: Box::new(field) }, span)
+                }
+                Some(Pattern::Punctuation(Punctuation::Question)) if CONDITIONAL_PRECEDENCE >= min => {
+                    self.advance();
+                    let if_true = self.parse_binary(0)?;
+                    self.expect(Punctuation::Colon)?;
+                    // the else arm takes further conditionals, which makes them right associative
+                    let if_false = self.parse_binary(CONDITIONAL_PRECEDENCE)?;
+                    let span = left.span.to(if_false.span);
+                    let conditional = ASTNode::Conditional { condition: Box::new(left), if_true: Box::new(if_true), if_false: Box::new(if_false) };
+                    SymbolNode::new(SymbolKind::Conditional, conditional, span)
+                }
+                Some(Pattern::Operator(operator)) if UPDATE_OPERATORS.contains(&operator) => {
+                    let span = left.span.to(self.advance().unwrap().span);
+                    let operation = UpdateOp::from_operator(operator).unwrap();
+                    let update = ASTNode::UpdateOperation { operation, operand: Box::new(left), prefix: false };
+                    SymbolNode::new(SymbolKind::UpdateOperation, update, span)
+                }
+                Some(Pattern::Operator(operator)) if BINARY_OPERATORS.contains(&operator) && operator.precedence() >= min => {
+                    self.advance();
+                    // the right operand only takes tighter operators, so equal ones associate to the left
+                    let right = self.parse_binary(operator.precedence() + 1)?;
+                    let span = left.span.to(right.span);
+                    let operation = BinOp::from_operator(operator).unwrap();
+                    let binary = ASTNode::BinaryOperation { left: Box::new(left), operation, right: Box::new(right) };
+                    SymbolNode::new(SymbolKind::BinaryOperation, binary, span)
+                }
+                _ => return Ok(left),
+            };
+        }
+    }
+
+    /// A literal, a call, a parenthesized expression, a cast or a prefix operator with its operand
+    fn parse_prefix(&mut self) -> Result<SymbolNode, ParseError> {
+        match self.peek_pattern(0) {
+            Some(Pattern::Operator(operator)) if UNARY_OPERATORS.contains(&operator) => {
+                let start = self.advance().unwrap().span;
+                let operand = self.parse_binary(UNARY_PRECEDENCE)?;
+                let span = start.to(operand.span);
+                let unary = ASTNode::UnaryOperation { operation: UnaryOp::from_operator(operator).unwrap(), operand: Box::new(operand) };
+                Ok(SymbolNode::new(SymbolKind::UnaryOperation, unary, span))
+            }
+            Some(Pattern::Operator(operator)) if UPDATE_OPERATORS.contains(&operator) => {
+                let start = self.advance().unwrap().span;
+                let operand = self.parse_binary(UNARY_PRECEDENCE)?;
+                let span = start.to(operand.span);
+                let operation = UpdateOp::from_operator(operator).unwrap();
+                let update = ASTNode::UpdateOperation { operation, operand: Box::new(operand), prefix: true };
+                Ok(SymbolNode::new(SymbolKind::UpdateOperation, update, span))
+            }
+            Some(Pattern::Punctuation(Punctuation::OpenParen)) => {
+                let open = self.advance().unwrap().span;
+                match self.peek_pattern(0) {
+                    Some(Pattern::Kind(SymbolKind::TypeIdentifier) | Pattern::Keyword(Keyword::Struct | Keyword::Enum)) => {
+                        let typ = self.parse_type_name()?;
+                        let operand = self.parse_binary(UNARY_PRECEDENCE)?;
+                        let span = open.to(operand.span);
+                        return Ok(SymbolNode::new(SymbolKind::Cast, ASTNode::Cast { typ, operand: Box::new(operand) }, span));
+                    }
+                    Some(pattern) if Self::expression_starts().contains(&pattern) => {}
+                    _ => {
+                        let type_starts = [
+                            Pattern::Kind(SymbolKind::TypeIdentifier),
+                            Pattern::Keyword(Keyword::Struct),
+                            Pattern::Keyword(Keyword::Enum),
+                        ];
+                        return Err(self.unexpected_pattern(Self::expression_starts().into_iter().chain(type_starts).collect()));
+                    }
+                }
+                let inner = self.parse_binary(0)?;
+                let close = self.expect(Punctuation::CloseParen)?;
+                Ok(SymbolNode { span: open.to(close), ..inner })
+            }
+            Some(Pattern::Kind(SymbolKind::Identifier))
+                if self.peek_pattern(1) == Some(Pattern::Punctuation(Punctuation::OpenParen)) =>
+            {
+                let name = self.advance().unwrap();
+                self.advance();
+                let (args, close) = self.parse_args()?;
+                let span = name.span.to(close);
+                Ok(SymbolNode::new(SymbolKind::Call, ASTNode::Call { name: Box::new(name), args }, span))
+            }
+            Some(Pattern::Kind(kind)) if LITERALS.contains(&kind) => Ok(self.advance().unwrap()),
+            _ => Err(self.unexpected_pattern(Self::expression_starts())),
+        }
+    }
+
+    /// The arguments of a call up to its `)`, the `(` being consumed already, and where the `)` is
+    fn parse_args(&mut self) -> Result<(Vec<SymbolNode>, Span), ParseError> {
+        let mut args = Vec::new();
+        if self.peek_pattern(0) == Some(Pattern::Punctuation(Punctuation::CloseParen)) {
+            let close = self.advance().unwrap().span;
+            return Ok((args, close));
+        }
+        loop {
+            args.push(self.parse_binary(0)?);
+            match self.peek_pattern(0) {
+                Some(Pattern::Punctuation(Punctuation::Comma)) => self.advance(),
+                Some(Pattern::Punctuation(Punctuation::CloseParen)) => {
+                    let close = self.advance().unwrap().span;
+                    return Ok((args, close));
+                }
+                _ => return Err(self.unexpected_after_operand(&[Punctuation::Comma, Punctuation::CloseParen])),
+            };
+        }
+    }
+
+    /// The type of a cast up to its `)`, e.g. `int` or `struct Point*`
+    fn parse_type_name(&mut self) -> Result<Type, ParseError> {
+        let mut typ = match self.advance().unwrap().get_value() {
+            ASTNode::Type(typ) => typ,
+            keyword => {
+                if self.peek_pattern(0) != Some(Pattern::Kind(SymbolKind::Identifier)) {
+                    return Err(self.unexpected(vec![Pattern::Kind(SymbolKind::Identifier).to_string()]));
+                }
+                let name = self.advance().unwrap().get_value().as_identifier().unwrap_or_default().to_string();
+                match keyword.as_keyword() {
+                    Some(Keyword::Enum) => Type::Enum(name),
+                    _ => Type::Struct(name),
+                }
+            }
+        };
+        loop {
+            match self.peek_pattern(0) {
+                Some(Pattern::Operator(OperatorType::Mul)) => {
+                    self.advance();
+                    typ = Type::Pointer(Box::new(typ));
+                }
+                Some(Pattern::Punctuation(Punctuation::CloseParen)) => {
+                    self.advance();
+                    return Ok(typ);
+                }
+                _ => {
+                    let expected = vec![Pattern::Operator(OperatorType::Mul), Pattern::Punctuation(Punctuation::CloseParen)];
+                    return Err(self.unexpected_pattern(expected));
+                }
+            }
+        }
+    }
+
+    /// Consumes the punctuation, giving where it is written
+    fn expect(&mut self, punctuation: Punctuation) -> Result<Span, ParseError> {
+        if self.peek_pattern(0) == Some(Pattern::Punctuation(punctuation)) {
+            Ok(self.advance().unwrap().span)
+        } else {
+            Err(self.unexpected_after_operand(&[punctuation]))
+        }
+    }
+
+    /// After an operand, the expression could also have gone on with an operator or a postfix form
+    fn unexpected_after_operand(&mut self, closing: &[Punctuation]) -> ParseError {
+        let continuations = [Punctuation::OpenBracket, Punctuation::Dot, Punctuation::Question].into_iter().chain(closing.iter().copied());
+        let expected = BINARY_OPERATORS
+            .into_iter()
+            .chain(UPDATE_OPERATORS)
+            .map(Pattern::Operator)
+            .chain(continuations.map(Pattern::Punctuation))
+            .collect();
+        self.unexpected_pattern(expected)
+    }
+
+    /// Lists the expected terminals the way the table does, sorted and without duplicates
+    fn unexpected_pattern(&mut self, expected: Vec<Pattern>) -> ParseError {
+        let mut expected: Vec<String> = expected.iter().map(Pattern::to_string).collect();
+        expected.sort();
+        expected.dedup();
+        self.unexpected(expected)
+    }
+
+    fn peek_pattern(&mut self, n: usize) -> Option<Pattern> {
+        self.peek(n).map(Pattern::of)
+    }
+
+    /// Terminals an expression can begin with
+    fn expression_starts() -> Vec<Pattern> {
+        LITERALS
+            .map(Pattern::Kind)
+            .into_iter()
+            .chain([Pattern::Punctuation(Punctuation::OpenParen)])
+            .chain(UNARY_OPERATORS.into_iter().chain(UPDATE_OPERATORS).map(Pattern::Operator))
+            .collect()
+    }
+}
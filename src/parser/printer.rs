@@ -0,0 +1,243 @@
+use super::program::{Declaration, Expr, ExprKind, Function, Item, ItemKind, Program, Stmt, StmtKind};
+use super::rules::{CONDITIONAL_PRECEDENCE, POSTFIX_PRECEDENCE, UNARY_PRECEDENCE};
+use super::types::Type;
+
+/*
+structure:
+Prints a `Program` back as source code, laid out the same way whatever the layout it was read from:
+one declaration or statement per line, four spaces of indentation per block, and a blank line around
+functions, structs and enums. An operand is put in parentheses only where it binds looser than its
+place in the operator around it allows, so that parsing the printed program gives the program again.
+Declaration lists are printed as one declaration per name, the way they are lowered, and type aliases
+as the type they stand for. Error nodes stand for input the parser skipped and are left out.
+ */
+
+const INDENT: &str = "    ";
+/// Literals, names and calls, which never need parentheses
+const PRIMARY_PRECEDENCE: u8 = POSTFIX_PRECEDENCE + 1;
+
+/// The source of a whole program
+pub fn print_program(program: &Program) -> String {
+    let mut printer = Printer::default();
+    let items: Vec<&Item> = program.items.iter().filter(|item| !matches!(item.kind, ItemKind::Error)).collect();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 && (is_block(items[i - 1]) || is_block(item)) {
+            printer.out.push('\n');
+        }
+        printer.item(item, "");
+    }
+    printer.out
+}
+
+/// The source of an expression, as it would be written on its own
+pub fn print_expr(expr: &Expr) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, expr, 0);
+    out
+}
+
+fn is_block(item: &Item) -> bool {
+    match &item.kind {
+        ItemKind::Function(_) | ItemKind::Struct { .. } | ItemKind::Enum { .. } => true,
+        ItemKind::Public(item) => is_block(item),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&INDENT.repeat(self.depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// `prefix` is written before the item, `pub ` for a public one
+    fn item(&mut self, item: &Item, prefix: &str) {
+        match &item.kind {
+            ItemKind::Global(declaration) => self.line(&format!("{}{};", prefix, print_declaration(declaration))),
+            ItemKind::Function(function) => self.function(function, prefix),
+            ItemKind::Struct { name, fields } => {
+                self.line(&format!("{}struct {} {{", prefix, name));
+                self.depth += 1;
+                for field in fields {
+                    self.line(&format!("{};", declarator(&field.typ, &field.name)));
+                }
+                self.depth -= 1;
+                self.line("};");
+            }
+            ItemKind::Enum { name, variants } if variants.is_empty() => self.line(&format!("{}enum {} {{}};", prefix, name)),
+            ItemKind::Enum { name, variants } => {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|variant| match &variant.value {
+                        Some(value) => format!("{} = {}", variant.name, print_expr(value)),
+                        None => variant.name.clone(),
+                    })
+                    .collect();
+                self.line(&format!("{}enum {} {{ {} }};", prefix, name, variants.join(", ")));
+            }
+            ItemKind::TypeAlias { name, typ } => self.line(&format!("{}typedef {};", prefix, declarator(typ, name))),
+            ItemKind::Import { path } => self.line(&format!("import \"{}\";", path)),
+            ItemKind::Public(item) => self.item(item, "pub "),
+            ItemKind::Statement(statement) => self.statement(statement),
+            ItemKind::Error => {}
+        }
+    }
+
+    fn function(&mut self, function: &Function, prefix: &str) {
+        let params: Vec<String> = function.params.iter().map(|param| declarator(&param.typ, &param.name)).collect();
+        let header = declarator(&function.return_type, &function.name);
+        self.line(&format!("{}{}({}) {{", prefix, header, params.join(", ")));
+        self.block(&function.body);
+        self.line("}");
+    }
+
+    fn block(&mut self, statements: &[Stmt]) {
+        self.depth += 1;
+        statements.iter().for_each(|statement| self.statement(statement));
+        self.depth -= 1;
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Declaration(declaration) => self.line(&format!("{};", print_declaration(declaration))),
+            StmtKind::Assign { target, value } => self.line(&format!("{} = {};", print_expr(target), print_expr(value))),
+            StmtKind::CompoundAssign { target, operation, value } => {
+                let operator = operation.operator().as_str();
+                self.line(&format!("{} {} {};", print_expr(target), operator, print_expr(value)))
+            }
+            StmtKind::Expr(expr) => self.line(&format!("{};", print_expr(expr))),
+            StmtKind::Return(Some(value)) => self.line(&format!("return {};", print_expr(value))),
+            StmtKind::Return(None) => self.line("return;"),
+            StmtKind::Switch { value, cases } => {
+                self.line(&format!("switch ({}) {{", print_expr(value)));
+                self.depth += 1;
+                for case in cases {
+                    match &case.value {
+                        Some(value) => self.line(&format!("case {}:", print_expr(value))),
+                        None => self.line("default:"),
+                    }
+                    self.block(&case.body);
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+            StmtKind::Break => self.line("break;"),
+            StmtKind::Error => {}
+        }
+    }
+}
+
+/// `const int x = 1` without its `;`
+fn print_declaration(declaration: &Declaration) -> String {
+    let constant = if declaration.constant { "const " } else { "" };
+    let declarator = declarator(&declaration.typ, &declaration.name);
+    match &declaration.value {
+        Some(value) => format!("{}{} = {}", constant, declarator, print_expr(value)),
+        None => format!("{}{}", constant, declarator),
+    }
+}
+
+/// A name with its type, the lengths of an array type written after the name: `int a[2][3]`
+fn declarator(typ: &Type, name: &str) -> String {
+    let mut dimensions = String::new();
+    let mut element = typ;
+    while let Type::Array(inner, length) = element {
+        dimensions.push_str(&format!("[{}]", length));
+        element = inner;
+    }
+    format!("{} {}{}", element, name, dimensions)
+}
+
+/// How tightly an expression holds together, with the precedences of the grammar
+fn precedence(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::Conditional { .. } => CONDITIONAL_PRECEDENCE,
+        ExprKind::Binary { operation, .. } => operation.precedence(),
+        ExprKind::Unary { .. } | ExprKind::Cast { .. } | ExprKind::Update { prefix: true, .. } => UNARY_PRECEDENCE,
+        ExprKind::Update { prefix: false, .. } | ExprKind::Index { .. } | ExprKind::Member { .. } => POSTFIX_PRECEDENCE,
+        ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Char(_)
+        | ExprKind::Identifier(_)
+        | ExprKind::Call { .. }
+        | ExprKind::Error => PRIMARY_PRECEDENCE,
+    }
+}
+
+/// Writes the expression, in parentheses when it binds looser than `min`
+fn write_expr(out: &mut String, expr: &Expr, min: u8) {
+    if precedence(expr) < min {
+        out.push('(');
+        write_expr(out, expr, 0);
+        out.push(')');
+        return;
+    }
+    match &expr.kind {
+        ExprKind::Number(n) => out.push_str(&n.to_string()),
+        ExprKind::String(s) => out.push_str(&format!("\"{}\"", s)),
+        ExprKind::Bool(b) => out.push_str(&b.to_string()),
+        ExprKind::Char(c) => out.push_str(&format!("'{}'", c)),
+        ExprKind::Identifier(name) => out.push_str(name),
+        // left associative: an equal operator on the left keeps its place, on the right it needs parentheses
+        ExprKind::Binary { left, operation, right } => {
+            write_expr(out, left, operation.precedence());
+            out.push_str(&format!(" {} ", operation.operator().as_str()));
+            write_expr(out, right, operation.precedence() + 1);
+        }
+        ExprKind::Unary { operation, operand } => {
+            out.push_str(operation.operator().as_str());
+            write_expr(out, operand, UNARY_PRECEDENCE);
+        }
+        ExprKind::Update { operation, operand, prefix: true } => {
+            out.push_str(operation.operator().as_str());
+            write_expr(out, operand, UNARY_PRECEDENCE);
+        }
+        ExprKind::Update { operation, operand, prefix: false } => {
+            write_expr(out, operand, POSTFIX_PRECEDENCE);
+            out.push_str(operation.operator().as_str());
+        }
+        // right associative: a conditional needs parentheses as the condition only
+        ExprKind::Conditional { condition, if_true, if_false } => {
+            write_expr(out, condition, CONDITIONAL_PRECEDENCE + 1);
+            out.push_str(" ? ");
+            write_expr(out, if_true, 0);
+            out.push_str(" : ");
+            write_expr(out, if_false, CONDITIONAL_PRECEDENCE);
+        }
+        ExprKind::Cast { typ, operand } => {
+            out.push_str(&format!("({}) ", typ));
+            write_expr(out, operand, UNARY_PRECEDENCE);
+        }
+        ExprKind::Call { name, args } => {
+            out.push_str(name);
+            out.push('(');
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_expr(out, arg, 0);
+            }
+            out.push(')');
+        }
+        ExprKind::Index { array, index } => {
+            write_expr(out, array, POSTFIX_PRECEDENCE);
+            out.push('[');
+            write_expr(out, index, 0);
+            out.push(']');
+        }
+        ExprKind::Member { object, field } => {
+            write_expr(out, object, POSTFIX_PRECEDENCE);
+            out.push('.');
+            out.push_str(field);
+        }
+        ExprKind::Error => {}
+    }
+}
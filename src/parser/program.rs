@@ -0,0 +1,765 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::ast::{ASTNode, SymbolNode};
+use super::span::Span;
+use super::types::{OperatorType, Type};
+use super::visit::{
+    walk_case_mut, walk_declaration_mut, walk_expr_mut, walk_field_mut, walk_function_mut, walk_item_mut, walk_param_mut,
+    walk_stmt_mut, walk_variant_mut, VisitorMut,
+};
+
+/*
+structure:
+The tree the later stages work on. The parser builds `ASTNode`s on its stack, mixing tokens such as
+operators and punctuation with the nodes made of them; once a top-level item is complete it is lowered
+into an `Item` here, where every field holds the kind of node it allows (an operator field one of the
+operators of its node, not any token) and the parentheses of the parse are gone. Each node keeps the
+span of the symbols it was lowered from, so that the later stages can tell where an error is.
+ */
+
+/// A whole program, its items in the order they are written
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Program {
+    pub items: Vec<Item>,
+}
+
+/// A node and where it is written; nodes compare their spans too, `Program::without_spans` clears them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub span: Span,
+    pub module: Option<String>, // the imported module the item comes from, set by the module loader
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemKind {
+    Global(Declaration),
+    Function(Function),
+    Struct { name: String, fields: Vec<Field> },
+    Enum { name: String, variants: Vec<Variant> },
+    TypeAlias { name: String, typ: Type },
+    Import { path: String },
+    Public(Box<Item>), // visible to the modules that import this one
+    Statement(Stmt),   // an assignment outside of any function, which the semantics rejects
+    Error,             // replaces the input skipped after a syntax error
+}
+
+/// `int x;`, `const int x = 1;`, every name of `int a, b;` is declared on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub typ: Type,
+    pub name: String,
+    pub value: Option<Expr>,
+    pub constant: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub return_type: Type,
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub typ: Type,
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub typ: Type,
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub value: Option<Expr>, // one more than the variant before when not given
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StmtKind {
+    Declaration(Declaration),
+    Assign { target: Expr, value: Expr },
+    CompoundAssign { target: Expr, operation: AssignOp, value: Expr }, // `+=` applies `Add`, `<<=` `ShiftLeft`, ...
+    Expr(Expr),
+    Return(Option<Expr>),
+    Switch { value: Expr, cases: Vec<Case> },
+    Break,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub value: Option<Expr>, // `None` for `default`
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    Number(i32),
+    String(String),
+    Bool(bool),
+    Char(char),
+    Identifier(String),
+    Binary { left: Box<Expr>, operation: BinOp, right: Box<Expr> },
+    Unary { operation: UnaryOp, operand: Box<Expr> },
+    Update { operation: UpdateOp, operand: Box<Expr>, prefix: bool }, // `++x` is the new value, `x++` the old one
+    Conditional { condition: Box<Expr>, if_true: Box<Expr>, if_false: Box<Expr> },
+    Cast { typ: Type, operand: Box<Expr> },
+    Call { name: String, args: Vec<Expr> },
+    Index { array: Box<Expr>, index: Box<Expr> },
+    Member { object: Box<Expr>, field: String },
+    Error, // a literal that has no value
+}
+
+/// The operation of a binary expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// The operation a compound assignment applies to its target, some of them have no binary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssignOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Deref,     // `*p`
+    AddressOf, // `&x`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateOp {
+    Increment,
+    Decrement,
+}
+
+impl BinOp {
+    /// The operation written as a binary operator, `None` for any other operator
+    pub fn from_operator(operator: OperatorType) -> Option<BinOp> {
+        match operator {
+            OperatorType::Add => Some(BinOp::Add),
+            OperatorType::Sub => Some(BinOp::Sub),
+            OperatorType::Mul => Some(BinOp::Mul),
+            OperatorType::Div => Some(BinOp::Div),
+            _ => None,
+        }
+    }
+
+    /// The operator the operation is written with, `+` for `Add`
+    pub fn operator(self) -> OperatorType {
+        match self {
+            BinOp::Add => OperatorType::Add,
+            BinOp::Sub => OperatorType::Sub,
+            BinOp::Mul => OperatorType::Mul,
+            BinOp::Div => OperatorType::Div,
+        }
+    }
+
+    /// Binding strength in a binary expression
+    pub fn precedence(self) -> u8 {
+        self.operator().precedence()
+    }
+}
+
+impl AssignOp {
+    /// The operation of a compound assignment operator, `Add` for `+=`
+    pub fn from_operator(operator: OperatorType) -> Option<AssignOp> {
+        match operator {
+            OperatorType::AddAssign => Some(AssignOp::Add),
+            OperatorType::SubAssign => Some(AssignOp::Sub),
+            OperatorType::MulAssign => Some(AssignOp::Mul),
+            OperatorType::DivAssign => Some(AssignOp::Div),
+            OperatorType::ModAssign => Some(AssignOp::Mod),
+            OperatorType::AndAssign => Some(AssignOp::BitAnd),
+            OperatorType::OrAssign => Some(AssignOp::BitOr),
+            OperatorType::XorAssign => Some(AssignOp::BitXor),
+            OperatorType::ShiftLeftAssign => Some(AssignOp::ShiftLeft),
+            OperatorType::ShiftRightAssign => Some(AssignOp::ShiftRight),
+            _ => None,
+        }
+    }
+
+    /// The compound assignment operator, `+=` for `Add`
+    pub fn operator(self) -> OperatorType {
+        match self {
+            AssignOp::Add => OperatorType::AddAssign,
+            AssignOp::Sub => OperatorType::SubAssign,
+            AssignOp::Mul => OperatorType::MulAssign,
+            AssignOp::Div => OperatorType::DivAssign,
+            AssignOp::Mod => OperatorType::ModAssign,
+            AssignOp::BitAnd => OperatorType::AndAssign,
+            AssignOp::BitOr => OperatorType::OrAssign,
+            AssignOp::BitXor => OperatorType::XorAssign,
+            AssignOp::ShiftLeft => OperatorType::ShiftLeftAssign,
+            AssignOp::ShiftRight => OperatorType::ShiftRightAssign,
+        }
+    }
+
+    /// The binary operation `x op= y` stands for, `None` where there is no binary operator for it
+    pub fn binary(self) -> Option<BinOp> {
+        match self {
+            AssignOp::Add => Some(BinOp::Add),
+            AssignOp::Sub => Some(BinOp::Sub),
+            AssignOp::Mul => Some(BinOp::Mul),
+            AssignOp::Div => Some(BinOp::Div),
+            _ => None,
+        }
+    }
+}
+
+impl UnaryOp {
+    pub fn from_operator(operator: OperatorType) -> Option<UnaryOp> {
+        match operator {
+            OperatorType::Mul => Some(UnaryOp::Deref),
+            OperatorType::AddressOf => Some(UnaryOp::AddressOf),
+            _ => None,
+        }
+    }
+
+    pub fn operator(self) -> OperatorType {
+        match self {
+            UnaryOp::Deref => OperatorType::Mul,
+            UnaryOp::AddressOf => OperatorType::AddressOf,
+        }
+    }
+}
+
+impl UpdateOp {
+    pub fn from_operator(operator: OperatorType) -> Option<UpdateOp> {
+        match operator {
+            OperatorType::Increment => Some(UpdateOp::Increment),
+            OperatorType::Decrement => Some(UpdateOp::Decrement),
+            _ => None,
+        }
+    }
+
+    pub fn operator(self) -> OperatorType {
+        match self {
+            UpdateOp::Increment => OperatorType::Increment,
+            UpdateOp::Decrement => OperatorType::Decrement,
+        }
+    }
+}
+
+impl Item {
+    pub fn new(kind: ItemKind, span: Span) -> Item {
+        Item { kind, span, module: None }
+    }
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Stmt {
+        Stmt { kind, span }
+    }
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Expr {
+        Expr { kind, span }
+    }
+}
+
+// nodes built by hand, e.g. in tests, are not written anywhere
+impl From<ItemKind> for Item {
+    fn from(kind: ItemKind) -> Item {
+        Item::new(kind, Span::default())
+    }
+}
+
+impl From<StmtKind> for Stmt {
+    fn from(kind: StmtKind) -> Stmt {
+        Stmt::new(kind, Span::default())
+    }
+}
+
+impl From<ExprKind> for Expr {
+    fn from(kind: ExprKind) -> Expr {
+        Expr::new(kind, Span::default())
+    }
+}
+
+impl Program {
+    /// Lowers the top-level items handed out by the parser
+    pub fn from_symbols(symbols: Vec<SymbolNode>) -> Program {
+        let mut items = Vec::new();
+        for symbol in symbols {
+            Item::lower(symbol, &mut items);
+        }
+        Program { items }
+    }
+
+    /// The same program with every span cleared, so that it equals the program read from any layout
+    /// of the same source, or one built from kinds alone
+    pub fn without_spans(mut self) -> Program {
+        ClearSpans.visit_program_mut(&mut self);
+        self
+    }
+}
+
+/// Resets every span to `Span::default()`, the span a node built from its kind has
+struct ClearSpans;
+
+impl VisitorMut for ClearSpans {
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        item.span = Span::default();
+        walk_item_mut(self, item);
+    }
+
+    fn visit_declaration_mut(&mut self, declaration: &mut Declaration) {
+        declaration.span = Span::default();
+        walk_declaration_mut(self, declaration);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        function.span = Span::default();
+        walk_function_mut(self, function);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        param.span = Span::default();
+        walk_param_mut(self, param);
+    }
+
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        field.span = Span::default();
+        walk_field_mut(self, field);
+    }
+
+    fn visit_variant_mut(&mut self, variant: &mut Variant) {
+        variant.span = Span::default();
+        walk_variant_mut(self, variant);
+    }
+
+    fn visit_stmt_mut(&mut self, statement: &mut Stmt) {
+        statement.span = Span::default();
+        walk_stmt_mut(self, statement);
+    }
+
+    fn visit_case_mut(&mut self, case: &mut Case) {
+        case.span = Span::default();
+        walk_case_mut(self, case);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        expr.span = Span::default();
+        walk_expr_mut(self, expr);
+    }
+}
+
+impl Item {
+    /// Adds the items of a node, a declaration list gives one item per name
+    fn lower(symbol: SymbolNode, items: &mut Vec<Item>) {
+        let span = symbol.span;
+        let kind = match symbol.value {
+            ASTNode::Declaration { .. }
+            | ASTNode::DeclarationAssignment { .. }
+            | ASTNode::Declarations { .. }
+            | ASTNode::ConstDeclaration { .. } => {
+                let mut declarations = Vec::new();
+                Declaration::lower(symbol, false, &mut declarations);
+                items.extend(declarations.into_iter().map(|declaration| {
+                    let span = declaration.span;
+                    Item::new(ItemKind::Global(declaration), span)
+                }));
+                return;
+            }
+            ASTNode::Function { return_type, name, params, body } => ItemKind::Function(Function {
+                return_type,
+                name: into_name(*name),
+                params: params
+                    .into_iter()
+                    .filter_map(|param| match param.value {
+                        ASTNode::Declaration { typ, name } => Some(Param { typ, name: into_name(*name), span: param.span }),
+                        _ => None,
+                    })
+                    .collect(),
+                body: Stmt::lower_all(body),
+                span,
+            }),
+            ASTNode::StructDeclaration { name, fields } => ItemKind::Struct {
+                name: into_name(*name),
+                // fields skipped after a syntax error are left out
+                fields: fields
+                    .into_iter()
+                    .filter_map(|field| match field.value {
+                        ASTNode::Declaration { typ, name } => Some(Field { typ, name: into_name(*name), span: field.span }),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+            ASTNode::EnumDeclaration { name, variants } => ItemKind::Enum {
+                name: into_name(*name),
+                variants: variants
+                    .into_iter()
+                    .filter_map(|variant| match variant.value {
+                        ASTNode::EnumVariant { name, value } => Some(Variant {
+                            name: into_name(*name),
+                            value: value.map(|value| Expr::from(*value)),
+                            span: variant.span,
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+            ASTNode::TypeAlias { name, typ } => ItemKind::TypeAlias { name: into_name(*name), typ },
+            ASTNode::Import { path } => ItemKind::Import { path },
+            ASTNode::Public { item } => {
+                let mut public = Vec::new();
+                Item::lower(*item, &mut public);
+                items.extend(public.into_iter().map(|item| Item::new(ItemKind::Public(Box::new(item)), span)));
+                return;
+            }
+            ASTNode::Assign { .. } | ASTNode::CompoundAssign { .. } => {
+                let mut statements = Vec::new();
+                Stmt::lower(symbol, &mut statements);
+                items.extend(statements.into_iter().map(|statement| Item::new(ItemKind::Statement(statement), span)));
+                return;
+            }
+            _ => ItemKind::Error,
+        };
+        items.push(Item::new(kind, span));
+    }
+}
+
+impl Declaration {
+    fn lower(symbol: SymbolNode, constant: bool, declarations: &mut Vec<Declaration>) {
+        let span = symbol.span;
+        match symbol.value {
+            ASTNode::Declaration { typ, name } => {
+                declarations.push(Declaration { typ, name: into_name(*name), value: None, constant, span })
+            }
+            ASTNode::DeclarationAssignment { typ, name, value } => declarations.push(Declaration {
+                typ,
+                name: into_name(*name),
+                value: Some(Expr::from(*value)),
+                constant,
+                span,
+            }),
+            ASTNode::Declarations { declarations: list, .. } => {
+                for declaration in list {
+                    Declaration::lower(declaration, constant, declarations);
+                }
+            }
+            ASTNode::ConstDeclaration { declaration } => Declaration::lower(*declaration, true, declarations),
+            other => unreachable!("{:?} is not a declaration", other),
+        }
+    }
+}
+
+impl Stmt {
+    fn lower_all(symbols: Vec<SymbolNode>) -> Vec<Stmt> {
+        let mut statements = Vec::new();
+        for symbol in symbols {
+            Stmt::lower(symbol, &mut statements);
+        }
+        statements
+    }
+
+    fn lower(symbol: SymbolNode, statements: &mut Vec<Stmt>) {
+        let span = symbol.span;
+        let kind = match symbol.value {
+            ASTNode::Declaration { .. }
+            | ASTNode::DeclarationAssignment { .. }
+            | ASTNode::Declarations { .. }
+            | ASTNode::ConstDeclaration { .. } => {
+                let mut declarations = Vec::new();
+                Declaration::lower(symbol, false, &mut declarations);
+                statements.extend(declarations.into_iter().map(|declaration| {
+                    let span = declaration.span;
+                    Stmt::new(StmtKind::Declaration(declaration), span)
+                }));
+                return;
+            }
+            ASTNode::Assign { name, value } => StmtKind::Assign { target: Expr::from(*name), value: Expr::from(*value) },
+            ASTNode::CompoundAssign { target, operation, value } => StmtKind::CompoundAssign {
+                target: Expr::from(*target),
+                operation,
+                value: Expr::from(*value),
+            },
+            ASTNode::Return { value } => StmtKind::Return(value.map(|value| Expr::from(*value))),
+            ASTNode::Switch { value, cases } => StmtKind::Switch {
+                value: Expr::from(*value),
+                cases: cases
+                    .into_iter()
+                    .filter_map(|case| match case.value {
+                        ASTNode::Case { value, body } => Some(Case {
+                            value: value.map(|value| Expr::from(*value)),
+                            body: Stmt::lower_all(body),
+                            span: case.span,
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+            },
+            ASTNode::Break() => StmtKind::Break,
+            ASTNode::Error() => StmtKind::Error,
+            value => StmtKind::Expr(Expr::from(SymbolNode { value, ..symbol })),
+        };
+        statements.push(Stmt::new(kind, span));
+    }
+}
+
+impl From<SymbolNode> for Expr {
+    fn from(symbol: SymbolNode) -> Expr {
+        let lower = |symbol: Box<SymbolNode>| Box::new(Expr::from(*symbol));
+        let kind = match symbol.value {
+            ASTNode::Number(n) => ExprKind::Number(n),
+            ASTNode::String(s) => ExprKind::String(s),
+            ASTNode::Bool(b) => ExprKind::Bool(b),
+            ASTNode::Char(c) => ExprKind::Char(c),
+            ASTNode::Identifier(name) => ExprKind::Identifier(name),
+            ASTNode::BinaryOperation { left, right, operation } => {
+                ExprKind::Binary { left: lower(left), operation, right: lower(right) }
+            }
+            ASTNode::UnaryOperation { operation, operand } => ExprKind::Unary { operation, operand: lower(operand) },
+            ASTNode::UpdateOperation { operation, operand, prefix } => {
+                ExprKind::Update { operation, operand: lower(operand), prefix }
+            }
+            ASTNode::Conditional { condition, if_true, if_false } => ExprKind::Conditional {
+                condition: lower(condition),
+                if_true: lower(if_true),
+                if_false: lower(if_false),
+            },
+            ASTNode::Cast { typ, operand } => ExprKind::Cast { typ, operand: lower(operand) },
+            ASTNode::Call { name, args } => {
+                ExprKind::Call { name: into_name(*name), args: args.into_iter().map(Expr::from).collect() }
+            }
+            ASTNode::Index { array, index } => ExprKind::Index { array: lower(array), index: lower(index) },
+            ASTNode::Member { object, field } => ExprKind::Member { object: lower(object), field: into_name(*field) },
+            ASTNode::Error() => ExprKind::Error,
+            other => unreachable!("{:?} is not an expression", other),
+        };
+        Expr::new(kind, symbol.span)
+    }
+}
+
+/// The grammar only puts identifiers where names are expected, an `Error` stands for a skipped one
+fn into_name(symbol: SymbolNode) -> String {
+    symbol.value.as_identifier().unwrap_or_default().to_string()
+}
+
+impl Expr {
+    pub fn inferred_type(&self) -> Option<Type> {
+        match &self.kind {
+            ExprKind::Number(_) => Some(Type::Integer),
+            ExprKind::String(_) => Some(Type::String),
+            ExprKind::Bool(_) => Some(Type::Bool),
+            ExprKind::Char(_) => Some(Type::Char),
+            _ => None,
+        }
+    }
+
+    /// Folds an expression made only of literals into its value, `None` if it is not constant
+    pub fn evaluate_constant(&self) -> Option<i32> {
+        self.evaluate_constant_in(&HashMap::new())
+    }
+
+    /// Like `evaluate_constant`, with the names in `constants` (enum variants) standing for their values
+    pub fn evaluate_constant_in(&self, constants: &HashMap<String, i32>) -> Option<i32> {
+        match &self.kind {
+            ExprKind::Number(n) => Some(*n),
+            ExprKind::Bool(b) => Some(*b as i32),
+            ExprKind::Char(c) => Some(*c as i32),
+            ExprKind::Identifier(name) => constants.get(name).copied(),
+            ExprKind::Cast { typ, operand } => {
+                let value = operand.evaluate_constant_in(constants)?;
+                match typ {
+                    Type::Integer | Type::Enum(_) => Some(value),
+                    Type::Char => Some(value as i8 as i32),
+                    Type::Bool => Some((value != 0) as i32),
+                    _ => None,
+                }
+            }
+            ExprKind::Conditional { condition, if_true, if_false } => match condition.evaluate_constant_in(constants)? {
+                0 => if_false.evaluate_constant_in(constants),
+                _ => if_true.evaluate_constant_in(constants),
+            },
+            ExprKind::Binary { left, operation, right } => {
+                let left = left.evaluate_constant_in(constants)?;
+                let right = right.evaluate_constant_in(constants)?;
+                match operation {
+                    BinOp::Add => left.checked_add(right),
+                    BinOp::Sub => left.checked_sub(right),
+                    BinOp::Mul => left.checked_mul(right),
+                    BinOp::Div => left.checked_div(right),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ItemKind::Global(declaration) => write!(f, "{}", declaration),
+            ItemKind::Function(function) => write!(f, "{}", function),
+            ItemKind::Struct { name, fields } => {
+                write!(f, "Struct({}, [", name)?;
+                write_list(f, fields)?;
+                write!(f, "])")
+            }
+            ItemKind::Enum { name, variants } => {
+                write!(f, "Enum({}, [", name)?;
+                write_list(f, variants)?;
+                write!(f, "])")
+            }
+            ItemKind::TypeAlias { name, typ } => write!(f, "TypeAlias({}, {:?})", name, typ),
+            ItemKind::Import { path } => write!(f, "Import(\"{}\")", path),
+            ItemKind::Public(item) => write!(f, "Public({})", item),
+            ItemKind::Statement(statement) => write!(f, "{}", statement),
+            ItemKind::Error => write!(f, "Error"),
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.constant {
+            write!(f, "Const")?;
+        }
+        match &self.value {
+            Some(value) => write!(f, "Declaration({:?}, {}, {})", self.typ, self.name, value),
+            None => write!(f, "Declaration({:?}, {})", self.typ, self.name),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Function({:?}, {}, [", self.return_type, self.name)?;
+        write_list(f, &self.params)?;
+        write!(f, "], [")?;
+        write_list(f, &self.body)?;
+        write!(f, "])")
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.typ, self.name)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.typ, self.name)
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} = {}", self.name, value),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            StmtKind::Declaration(declaration) => write!(f, "{}", declaration),
+            StmtKind::Assign { target, value } => write!(f, "Assign({}, {})", target, value),
+            StmtKind::CompoundAssign { target, operation, value } => {
+                write!(f, "CompoundAssign({}, {:?}, {})", target, operation, value)
+            }
+            StmtKind::Expr(expr) => write!(f, "{}", expr),
+            StmtKind::Return(Some(value)) => write!(f, "Return({})", value),
+            StmtKind::Return(None) => write!(f, "Return"),
+            StmtKind::Switch { value, cases } => {
+                write!(f, "Switch({}, [", value)?;
+                write_list(f, cases)?;
+                write!(f, "])")
+            }
+            StmtKind::Break => write!(f, "Break"),
+            StmtKind::Error => write!(f, "Error"),
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "Case({}, [", value)?,
+            None => write!(f, "Default([")?,
+        }
+        write_list(f, &self.body)?;
+        write!(f, "])")
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Number(n) => write!(f, "{}", n),
+            ExprKind::String(s) => write!(f, "\"{}\"", s),
+            ExprKind::Bool(b) => write!(f, "{}", b),
+            ExprKind::Char(c) => write!(f, "'{}'", c),
+            ExprKind::Identifier(name) => write!(f, "{}", name),
+            ExprKind::Binary { left, operation, right } => write!(f, "Binary({}, {:?}, {})", left, operation, right),
+            ExprKind::Unary { operation, operand } => write!(f, "Unary({:?}, {})", operation, operand),
+            ExprKind::Update { operation, operand, prefix: true } => write!(f, "Update({:?}, {})", operation, operand),
+            ExprKind::Update { operation, operand, prefix: false } => write!(f, "Update({}, {:?})", operand, operation),
+            ExprKind::Conditional { condition, if_true, if_false } => {
+                write!(f, "Conditional({}, {}, {})", condition, if_true, if_false)
+            }
+            ExprKind::Cast { typ, operand } => write!(f, "Cast({:?}, {})", typ, operand),
+            ExprKind::Call { name, args } => {
+                write!(f, "Call({}, [", name)?;
+                write_list(f, args)?;
+                write!(f, "])")
+            }
+            ExprKind::Index { array, index } => write!(f, "Index({}, {})", array, index),
+            ExprKind::Member { object, field } => write!(f, "Member({}, {})", object, field),
+            ExprKind::Error => write!(f, "Error"),
+        }
+    }
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, nodes: &[T]) -> fmt::Result {
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", node)?;
+    }
+    Ok(())
+}
@@ -1,76 +1,784 @@
 use crate::lexer::lexer::SymbolKind;
+use crate::parser::ast::{ASTNode, SymbolNode};
+use crate::parser::error::ParseError;
+use crate::parser::span::Span;
+use crate::parser::types::{Keyword, OperatorType, Punctuation};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// One element of a rule: either any symbol of a kind, or one specific token
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Pattern {
+    Kind(SymbolKind),
+    Punctuation(Punctuation),
+    Operator(OperatorType),
+    Keyword(Keyword),
+}
+
+impl Pattern {
+    /// The terminal a token is matched as
+    pub fn of(symbol: &SymbolNode) -> Pattern {
+        match &symbol.value {
+            ASTNode::Punctuation(p) => Pattern::Punctuation(*p),
+            ASTNode::Operator(op) => Pattern::Operator(*op),
+            ASTNode::Keyword(k) => Pattern::Keyword(*k),
+            _ => Pattern::Kind(symbol.kind),
+        }
+    }
+
+    /// The pattern as written in the rules, symbols by their kind rather than the words of the messages
+    pub fn grammar_name(&self) -> String {
+        match self {
+            Pattern::Kind(kind) => format!("{:?}", kind),
+            terminal => terminal.to_string(),
+        }
+    }
+
+    /// Binding strength of a terminal, used to pick between shifting it and reducing an expression
+    pub fn precedence(&self) -> Option<u8> {
+        match self {
+            Pattern::Operator(OperatorType::Increment | OperatorType::Decrement) => Some(POSTFIX_PRECEDENCE),
+            Pattern::Operator(op) => Some(op.precedence()),
+            Pattern::Punctuation(Punctuation::OpenBracket | Punctuation::Dot) => Some(POSTFIX_PRECEDENCE),
+            Pattern::Punctuation(Punctuation::Question) => Some(CONDITIONAL_PRECEDENCE),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Kind(SymbolKind::Identifier) => write!(f, "a name"),
+            Pattern::Kind(SymbolKind::TypeIdentifier) => write!(f, "a type"),
+            Pattern::Kind(SymbolKind::Number) => write!(f, "a number"),
+            Pattern::Kind(SymbolKind::String) => write!(f, "a string"),
+            Pattern::Kind(SymbolKind::Char) => write!(f, "a character"),
+            Pattern::Kind(SymbolKind::Bool) => write!(f, "a bool"),
+            Pattern::Kind(kind) => write!(f, "{:?}", kind),
+            Pattern::Punctuation(p) => write!(f, "'{}'", p.as_str()),
+            Pattern::Operator(op) => write!(f, "'{}'", op.as_str()),
+            Pattern::Keyword(k) => write!(f, "'{}'", k.as_str()),
+        }
+    }
+}
+
+/// Builds the node of the reduced symbol from the matched symbols (and the span they cover),
+/// or tells why the matched input is not valid although the grammar allows it
+pub type Action = fn(&mut VecDeque<SymbolNode>, Span) -> Result<ASTNode, ParseError>;
+
+pub struct Rule {
+    pub result: SymbolKind,
+    pub pattern: Vec<Pattern>,
+    pub action: Action,
+    pub precedence: Option<u8>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ->", self.result)?;
+        if self.pattern.is_empty() {
+            return write!(f, " (empty)");
+        }
+        for pattern in &self.pattern {
+            write!(f, " {}", pattern.grammar_name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Prefix `*`, `&` and casts bind tighter than any binary operator, `a[i]` and `p.x` tighter still
+pub const UNARY_PRECEDENCE: u8 = 4;
+pub const POSTFIX_PRECEDENCE: u8 = 5;
+/// `c ? a : b` binds looser than any binary operator
+pub const CONDITIONAL_PRECEDENCE: u8 = 1;
+
+pub const BINARY_OPERATORS: [OperatorType; 4] = [OperatorType::Add, OperatorType::Sub, OperatorType::Mul, OperatorType::Div];
+pub const UNARY_OPERATORS: [OperatorType; 2] = [OperatorType::Mul, OperatorType::AddressOf];
+/// Written before or after their operand
+pub const UPDATE_OPERATORS: [OperatorType; 2] = [OperatorType::Increment, OperatorType::Decrement];
+const COMPOUND_ASSIGNMENTS: [OperatorType; 10] = [
+    OperatorType::AddAssign,
+    OperatorType::SubAssign,
+    OperatorType::MulAssign,
+    OperatorType::DivAssign,
+    OperatorType::ModAssign,
+    OperatorType::AndAssign,
+    OperatorType::OrAssign,
+    OperatorType::XorAssign,
+    OperatorType::ShiftLeftAssign,
+    OperatorType::ShiftRightAssign,
+];
+
+/// Tokens that are an expression on their own
+pub const LITERALS: [SymbolKind; 5] = [
+    SymbolKind::Identifier,
+    SymbolKind::Number,
+    SymbolKind::Bool,
+    SymbolKind::String,
+    SymbolKind::Char,
+];
+
+/// The symbol a whole input reduces to
+pub const START: SymbolKind = SymbolKind::Program;
+
+/// Items that may appear at the top level of a program
+const ITEMS: [SymbolKind; 12] = [
+    SymbolKind::Declaration,
+    SymbolKind::DeclarationAssignment,
+    SymbolKind::Declarations,
+    SymbolKind::ConstDeclaration,
+    SymbolKind::Assign,
+    SymbolKind::CompoundAssign,
+    SymbolKind::Function,
+    SymbolKind::StructDeclaration,
+    SymbolKind::EnumDeclaration,
+    SymbolKind::TypeAlias,
+    SymbolKind::Import,
+    SymbolKind::Public,
+];
+
+/// Items that `pub` can make visible to the modules importing them
+const PUBLIC_ITEMS: [SymbolKind; 8] = [
+    SymbolKind::Declaration,
+    SymbolKind::DeclarationAssignment,
+    SymbolKind::Declarations,
+    SymbolKind::ConstDeclaration,
+    SymbolKind::Function,
+    SymbolKind::StructDeclaration,
+    SymbolKind::EnumDeclaration,
+    SymbolKind::TypeAlias,
+];
+
+/// Statements that may appear inside a function body
+const STATEMENTS: [SymbolKind; 11] = [
+    SymbolKind::Declaration,
+    SymbolKind::DeclarationAssignment,
+    SymbolKind::Declarations,
+    SymbolKind::ConstDeclaration,
+    SymbolKind::Assign,
+    SymbolKind::CompoundAssign,
+    SymbolKind::Return,
+    SymbolKind::Switch,
+    SymbolKind::Break,
+    SymbolKind::ExpressionStatement,
+    SymbolKind::ErrorStatement,
+];
+
+/// Tokens that end the input skipped after a syntax error
+const SYNCHRONIZING: [Punctuation; 2] = [Punctuation::Semicolon, Punctuation::CloseBrace];
+
+use Pattern::Kind;
+
+fn punct(p: Punctuation) -> Pattern {
+    Pattern::Punctuation(p)
+}
+
+/// Adds a rule, its precedence is the one of its last operator unless given
+fn add(rules: &mut Vec<Rule>, result: SymbolKind, pattern: Vec<Pattern>, action: Action, precedence: Option<u8>) {
+    let precedence = precedence.or_else(|| pattern.iter().rev().find_map(Pattern::precedence));
+    rules.push(Rule { result, pattern, action, precedence });
+}
 
 /*
 structure:
-Map by last symbol kind of the rule -> gets a list of all the rules that start with this symbol kind
--> every item in the list is a tuple of the LHS and RHS of the rule
+A flat list of productions, the parse table in `table.rs` is built from it once.
+Conflicts between shifting an operator and reducing an expression are decided by precedence,
+an equal precedence reduces (operators are left associative).
  */
-pub static RULES: Lazy<HashMap<SymbolKind, Vec<(SymbolKind, Vec<SymbolKind>)>>> = Lazy::new(|| {
-    let mut m = HashMap::new();
+pub static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    let mut m = Vec::new();
 
-    m.insert(
-        SymbolKind::Expr,
-        vec![(
+    // the top-level items are handed out by the parser as they are reduced
+    add(&mut m, SymbolKind::Program, vec![], |_, _| Ok(ASTNode::Empty()), None);
+    for item in ITEMS {
+        add(&mut m, SymbolKind::Program, vec![Kind(SymbolKind::Program), Kind(item)], |_, _| Ok(ASTNode::Empty()), None);
+    }
+    for token in SYNCHRONIZING {
+        add(
+            &mut m,
+            SymbolKind::Program,
+            vec![Kind(SymbolKind::Program), Kind(SymbolKind::Error), punct(token)],
+            |_, _| Ok(ASTNode::Empty()),
+            None,
+        );
+    }
+
+    // int x;  int* p;  struct Point q;
+    add(&mut m, SymbolKind::TypeName, vec![Kind(SymbolKind::TypeIdentifier)], |s, _| Ok(ASTNode::take_first(s)), None);
+    add(
+        &mut m,
+        SymbolKind::TypeName,
+        vec![Pattern::Keyword(Keyword::Struct), Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::create_struct_type(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::TypeName,
+        vec![Pattern::Keyword(Keyword::Enum), Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::create_enum_type(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::TypeName,
+        vec![Kind(SymbolKind::TypeName), Pattern::Operator(OperatorType::Mul)],
+        |s, _| Ok(ASTNode::create_pointer_type(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::DeclarationHead,
+        vec![Kind(SymbolKind::TypeName), Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::create_declaration(s)),
+        None,
+    );
+    // `foo x;` is still read as a declaration so that the unknown type gets reported
+    add(
+        &mut m,
+        SymbolKind::DeclarationHead,
+        vec![Kind(SymbolKind::Identifier), Kind(SymbolKind::Identifier)],
+        ASTNode::create_unknown_type_declaration,
+        None,
+    );
+
+    for operator in BINARY_OPERATORS {
+        add(
+            &mut m,
             SymbolKind::BinaryOperation,
-            vec![SymbolKind::Expr, SymbolKind::Operator, SymbolKind::Expr],
-        )],
+            vec![Kind(SymbolKind::Expr), Pattern::Operator(operator), Kind(SymbolKind::Expr)],
+            |s, _| Ok(ASTNode::create_binary_op(s)),
+            None,
+        );
+    }
+    // *p = &x;
+    for operator in UNARY_OPERATORS {
+        add(
+            &mut m,
+            SymbolKind::UnaryOperation,
+            vec![Pattern::Operator(operator), Kind(SymbolKind::Expr)],
+            |s, _| Ok(ASTNode::create_unary_op(s)),
+            Some(UNARY_PRECEDENCE),
+        );
+    }
+    // ++i;  a[i--]
+    for operator in UPDATE_OPERATORS {
+        add(
+            &mut m,
+            SymbolKind::UpdateOperation,
+            vec![Pattern::Operator(operator), Kind(SymbolKind::Expr)],
+            |s, _| Ok(ASTNode::create_prefix_update(s)),
+            Some(UNARY_PRECEDENCE),
+        );
+        add(
+            &mut m,
+            SymbolKind::UpdateOperation,
+            vec![Kind(SymbolKind::Expr), Pattern::Operator(operator)],
+            |s, _| Ok(ASTNode::create_postfix_update(s)),
+            None,
+        );
+    }
+    // a ? b : c ? d : e is a ? b : (c ? d : e), the rule ranks below its `?` so that another one is shifted
+    add(
+        &mut m,
+        SymbolKind::Conditional,
+        vec![
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::Question),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::Colon),
+            Kind(SymbolKind::Expr),
+        ],
+        |s, _| Ok(ASTNode::create_conditional(s)),
+        Some(CONDITIONAL_PRECEDENCE - 1),
+    );
+    // (char) n  (int*) p, binding like a prefix operator
+    add(
+        &mut m,
+        SymbolKind::Cast,
+        vec![
+            punct(Punctuation::OpenParen),
+            Kind(SymbolKind::TypeName),
+            punct(Punctuation::CloseParen),
+            Kind(SymbolKind::Expr),
+        ],
+        |s, _| Ok(ASTNode::create_cast(s)),
+        Some(UNARY_PRECEDENCE),
+    );
+    add(
+        &mut m,
+        SymbolKind::Member,
+        vec![Kind(SymbolKind::Expr), punct(Punctuation::Dot), Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::create_member(s)),
+        None,
     );
 
-    m.insert(
-        SymbolKind::Number,
-        vec![(SymbolKind::Expr, vec![SymbolKind::Number])],
+    // f(a, b + 1)
+    add(
+        &mut m,
+        SymbolKind::CallHeader,
+        vec![Kind(SymbolKind::Identifier), punct(Punctuation::OpenParen)],
+        |s, _| Ok(ASTNode::create_call_header(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::CallHeader,
+        vec![Kind(SymbolKind::CallHeader), Kind(SymbolKind::Expr), punct(Punctuation::Comma)],
+        |s, _| Ok(ASTNode::add_call_arg(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Call,
+        vec![Kind(SymbolKind::CallHeader), Kind(SymbolKind::Expr), punct(Punctuation::CloseParen)],
+        |s, _| Ok(ASTNode::add_call_arg(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Call,
+        vec![Kind(SymbolKind::CallHeader), punct(Punctuation::CloseParen)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
     );
-    m.insert(
-        SymbolKind::Bool,
-        vec![(SymbolKind::Expr, vec![SymbolKind::Bool])],
+    add(
+        &mut m,
+        SymbolKind::Expr,
+        vec![punct(Punctuation::OpenParen), Kind(SymbolKind::Expr), punct(Punctuation::CloseParen)],
+        |s, _| Ok(ASTNode::take_second(s)),
+        None,
     );
-    m.insert(
-        SymbolKind::String,
-        vec![(SymbolKind::Expr, vec![SymbolKind::String])],
+
+    // int a[10];  a[i]
+    add(
+        &mut m,
+        SymbolKind::DeclarationHead,
+        vec![
+            Kind(SymbolKind::DeclarationHead),
+            punct(Punctuation::OpenBracket),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::CloseBracket),
+        ],
+        ASTNode::create_array_declaration,
+        None,
     );
-    m.insert(
-        SymbolKind::Char,
-        vec![(SymbolKind::Expr, vec![SymbolKind::Char])],
+    add(
+        &mut m,
+        SymbolKind::Index,
+        vec![
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::OpenBracket),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::CloseBracket),
+        ],
+        |s, _| Ok(ASTNode::create_index(s)),
+        None,
     );
-    m.insert(
+
+    for literal in LITERALS.into_iter().chain([
         SymbolKind::BinaryOperation,
-        vec![(SymbolKind::Expr, vec![SymbolKind::BinaryOperation])],
+        SymbolKind::UnaryOperation,
+        SymbolKind::UpdateOperation,
+        SymbolKind::Conditional,
+        SymbolKind::Cast,
+        SymbolKind::Call,
+        SymbolKind::Index,
+        SymbolKind::Member,
+    ]) {
+        add(&mut m, SymbolKind::Expr, vec![Kind(literal)], |s, _| Ok(ASTNode::take_first(s)), None);
+    }
+
+    add(
+        &mut m,
+        SymbolKind::DeclarationAssignment,
+        vec![
+            Kind(SymbolKind::DeclarationHead),
+            Pattern::Operator(OperatorType::Equal),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::Semicolon),
+        ],
+        |s, _| Ok(ASTNode::create_declaration_assignment(s)),
+        None,
     );
-    m.insert(
-        SymbolKind::Punctuation,
+    add(
+        &mut m,
+        SymbolKind::Assign,
         vec![
+            Kind(SymbolKind::Expr),
+            Pattern::Operator(OperatorType::Equal),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::Semicolon),
+        ],
+        |s, _| Ok(ASTNode::create_assign(s)),
+        None,
+    );
+    // x += 1;
+    for operator in COMPOUND_ASSIGNMENTS {
+        add(
+            &mut m,
+            SymbolKind::CompoundAssign,
+            vec![Kind(SymbolKind::Expr), Pattern::Operator(operator), Kind(SymbolKind::Expr), punct(Punctuation::Semicolon)],
+            |s, _| Ok(ASTNode::create_compound_assign(s)),
+            None,
+        );
+    }
+    add(
+        &mut m,
+        SymbolKind::Return,
+        vec![Pattern::Keyword(Keyword::Return), Kind(SymbolKind::Expr), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::create_return(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Return,
+        vec![Pattern::Keyword(Keyword::Return), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::create_return(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Declaration,
+        vec![Kind(SymbolKind::DeclarationHead), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+
+    // int a[2], b = 1, c;
+    add(
+        &mut m,
+        SymbolKind::DeclarationList,
+        vec![Kind(SymbolKind::DeclarationHead), punct(Punctuation::Comma)],
+        |s, _| Ok(ASTNode::create_declaration_list(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::DeclarationList,
+        vec![
+            Kind(SymbolKind::DeclarationHead),
+            Pattern::Operator(OperatorType::Equal),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::Comma),
+        ],
+        |s, _| Ok(ASTNode::create_declaration_list(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::DeclaratorHead,
+        vec![Kind(SymbolKind::DeclarationList), Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::add_declarator(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::DeclaratorHead,
+        vec![
+            Kind(SymbolKind::DeclaratorHead),
+            punct(Punctuation::OpenBracket),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::CloseBracket),
+        ],
+        ASTNode::add_declarator_dimension,
+        None,
+    );
+    for (result, end) in [(SymbolKind::DeclarationList, Punctuation::Comma), (SymbolKind::Declarations, Punctuation::Semicolon)] {
+        add(
+            &mut m,
+            result,
+            vec![Kind(SymbolKind::DeclaratorHead), punct(end)],
+            |s, _| Ok(ASTNode::finish_declarator(s)),
+            None,
+        );
+        add(
+            &mut m,
+            result,
+            vec![
+                Kind(SymbolKind::DeclaratorHead),
+                Pattern::Operator(OperatorType::Equal),
+                Kind(SymbolKind::Expr),
+                punct(end),
+            ],
+            |s, _| Ok(ASTNode::finish_declarator(s)),
+            None,
+        );
+    }
+    // const int a = 1;
+    for declaration in [SymbolKind::Declaration, SymbolKind::DeclarationAssignment, SymbolKind::Declarations] {
+        add(
+            &mut m,
+            SymbolKind::ConstDeclaration,
+            vec![Pattern::Keyword(Keyword::Const), Kind(declaration)],
+            |s, _| Ok(ASTNode::create_const_declaration(s)),
+            None,
+        );
+    }
+    add(
+        &mut m,
+        SymbolKind::ExpressionStatement,
+        vec![Kind(SymbolKind::Expr), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+
+    // int add(int a, int b) { ... }
+    add(
+        &mut m,
+        SymbolKind::FunctionHeader,
+        vec![Kind(SymbolKind::FunctionHeader), Kind(SymbolKind::DeclarationHead), punct(Punctuation::Comma)],
+        |s, _| Ok(ASTNode::add_function_param(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::FunctionHeader,
+        vec![Kind(SymbolKind::DeclarationHead), punct(Punctuation::OpenParen)],
+        |s, _| Ok(ASTNode::create_function_header(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::FunctionSignature,
+        vec![Kind(SymbolKind::FunctionHeader), Kind(SymbolKind::DeclarationHead), punct(Punctuation::CloseParen)],
+        |s, _| Ok(ASTNode::add_function_param(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::FunctionSignature,
+        vec![Kind(SymbolKind::FunctionHeader), punct(Punctuation::CloseParen)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::FunctionBody,
+        vec![Kind(SymbolKind::FunctionSignature), punct(Punctuation::OpenBrace)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Function,
+        vec![Kind(SymbolKind::FunctionBody), punct(Punctuation::CloseBrace)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+    for statement in STATEMENTS {
+        add(
+            &mut m,
+            SymbolKind::FunctionBody,
+            vec![Kind(SymbolKind::FunctionBody), Kind(statement)],
+            |s, _| Ok(ASTNode::add_function_statement(s)),
+            None,
+        );
+    }
 
-            (
-                SymbolKind::DeclarationAssignment,
-                vec![
-                    SymbolKind::Identifier,
-                    SymbolKind::Identifier,
-                    SymbolKind::Operator,
-                    SymbolKind::Expr,
-                    SymbolKind::Punctuation,
-                ],
-            ),
-            (
-                SymbolKind::Declaration,
-                vec![
-                    SymbolKind::Identifier,
-                    SymbolKind::Identifier,
-                    SymbolKind::Punctuation,
-                ],
-            ),
-            (
-                SymbolKind::Assign,
-                vec![
-                    SymbolKind::Identifier,
-                    SymbolKind::Operator,
-                    SymbolKind::Expr,
-                    SymbolKind::Punctuation,
-                ],
-            ),
+    // switch (x) { case 1: case 2: y = 1; break; default: y = 2; }
+    add(
+        &mut m,
+        SymbolKind::SwitchHeader,
+        vec![
+            Pattern::Keyword(Keyword::Switch),
+            punct(Punctuation::OpenParen),
+            Kind(SymbolKind::Expr),
+            punct(Punctuation::CloseParen),
+            punct(Punctuation::OpenBrace),
         ],
+        |s, _| Ok(ASTNode::create_switch_header(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::CaseLabel,
+        vec![Pattern::Keyword(Keyword::Case), Kind(SymbolKind::Expr), punct(Punctuation::Colon)],
+        |s, _| Ok(ASTNode::create_case_label(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::CaseLabel,
+        vec![Pattern::Keyword(Keyword::Default), punct(Punctuation::Colon)],
+        |s, _| Ok(ASTNode::create_case_label(s)),
+        None,
+    );
+    // the first thing in a switch must be a label, statements then go to the last one
+    for open in [SymbolKind::SwitchHeader, SymbolKind::SwitchBody] {
+        add(
+            &mut m,
+            SymbolKind::SwitchBody,
+            vec![Kind(open), Kind(SymbolKind::CaseLabel)],
+            |s, _| Ok(ASTNode::add_switch_case(s)),
+            None,
+        );
+    }
+    for statement in STATEMENTS {
+        add(
+            &mut m,
+            SymbolKind::SwitchBody,
+            vec![Kind(SymbolKind::SwitchBody), Kind(statement)],
+            |s, _| Ok(ASTNode::add_switch_statement(s)),
+            None,
+        );
+    }
+    for open in [SymbolKind::SwitchHeader, SymbolKind::SwitchBody] {
+        add(
+            &mut m,
+            SymbolKind::Switch,
+            vec![Kind(open), punct(Punctuation::CloseBrace)],
+            |s, _| Ok(ASTNode::take_first(s)),
+            None,
+        );
+    }
+    add(
+        &mut m,
+        SymbolKind::Break,
+        vec![Pattern::Keyword(Keyword::Break), punct(Punctuation::Semicolon)],
+        |_, _| Ok(ASTNode::Break()),
+        None,
+    );
+
+    // a statement or field skipped up to its ';', or the rest of a body skipped up to its '}'
+    add(
+        &mut m,
+        SymbolKind::ErrorStatement,
+        vec![Kind(SymbolKind::Error), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Function,
+        vec![Kind(SymbolKind::FunctionBody), Kind(SymbolKind::Error), punct(Punctuation::CloseBrace)],
+        |s, _| Ok(ASTNode::add_function_statement(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::Switch,
+        vec![Kind(SymbolKind::SwitchBody), Kind(SymbolKind::Error), punct(Punctuation::CloseBrace)],
+        |s, _| Ok(ASTNode::add_switch_statement(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::StructDeclaration,
+        vec![
+            Kind(SymbolKind::StructHeader),
+            Kind(SymbolKind::Error),
+            punct(Punctuation::CloseBrace),
+            punct(Punctuation::Semicolon),
+        ],
+        |s, _| Ok(ASTNode::add_struct_field(s)),
+        None,
+    );
+
+    // struct Point { int x; int y; };
+    add(
+        &mut m,
+        SymbolKind::StructHeader,
+        vec![
+            Pattern::Keyword(Keyword::Struct),
+            Kind(SymbolKind::Identifier),
+            punct(Punctuation::OpenBrace),
+        ],
+        |s, _| Ok(ASTNode::create_struct_header(s)),
+        None,
+    );
+    for field in [SymbolKind::Declaration, SymbolKind::Declarations, SymbolKind::ErrorStatement] {
+        add(
+            &mut m,
+            SymbolKind::StructHeader,
+            vec![Kind(SymbolKind::StructHeader), Kind(field)],
+            |s, _| Ok(ASTNode::add_struct_field(s)),
+            None,
+        );
+    }
+    add(
+        &mut m,
+        SymbolKind::StructDeclaration,
+        vec![
+            Kind(SymbolKind::StructHeader),
+            punct(Punctuation::CloseBrace),
+            punct(Punctuation::Semicolon),
+        ],
+        |s, _| Ok(ASTNode::take_first(s)),
+        None,
+    );
+
+    // typedef int Id;  typedef char Name[16];
+    add(
+        &mut m,
+        SymbolKind::TypeAlias,
+        vec![Pattern::Keyword(Keyword::Typedef), Kind(SymbolKind::DeclarationHead), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::create_type_alias(s)),
+        None,
+    );
+
+    // import "math.src";
+    add(
+        &mut m,
+        SymbolKind::Import,
+        vec![Pattern::Keyword(Keyword::Import), Kind(SymbolKind::String), punct(Punctuation::Semicolon)],
+        |s, _| Ok(ASTNode::create_import(s)),
+        None,
+    );
+
+    // pub int square(int x) { ... }  pub struct Point { ... };
+    for item in PUBLIC_ITEMS {
+        add(&mut m, SymbolKind::Public, vec![Pattern::Keyword(Keyword::Pub), Kind(item)], |s, _| Ok(ASTNode::create_public(s)), None);
+    }
+
+    // enum Color { Red, Green = 5, Blue };
+    add(
+        &mut m,
+        SymbolKind::EnumHeader,
+        vec![Pattern::Keyword(Keyword::Enum), Kind(SymbolKind::Identifier), punct(Punctuation::OpenBrace)],
+        |s, _| Ok(ASTNode::create_enum_header(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::EnumVariant,
+        vec![Kind(SymbolKind::Identifier)],
+        |s, _| Ok(ASTNode::create_enum_variant(s)),
+        None,
+    );
+    add(
+        &mut m,
+        SymbolKind::EnumVariant,
+        vec![Kind(SymbolKind::Identifier), Pattern::Operator(OperatorType::Equal), Kind(SymbolKind::Expr)],
+        |s, _| Ok(ASTNode::create_enum_variant(s)),
+        None,
     );
+    for open in [SymbolKind::EnumHeader, SymbolKind::EnumBody] {
+        add(
+            &mut m,
+            SymbolKind::EnumBody,
+            vec![Kind(open), Kind(SymbolKind::EnumVariant), punct(Punctuation::Comma)],
+            |s, _| Ok(ASTNode::add_enum_variant(s)),
+            None,
+        );
+        add(
+            &mut m,
+            SymbolKind::EnumDeclaration,
+            vec![Kind(open), Kind(SymbolKind::EnumVariant), punct(Punctuation::CloseBrace), punct(Punctuation::Semicolon)],
+            |s, _| Ok(ASTNode::add_enum_variant(s)),
+            None,
+        );
+        // no variant at all, or a comma after the last one
+        add(
+            &mut m,
+            SymbolKind::EnumDeclaration,
+            vec![Kind(open), punct(Punctuation::CloseBrace), punct(Punctuation::Semicolon)],
+            |s, _| Ok(ASTNode::take_first(s)),
+            None,
+        );
+    }
 
     m
 });
@@ -0,0 +1,32 @@
+use std::fmt;
+
+/// Where a token or node is written, from its first character to its last (lines and columns from 1)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl Span {
+    pub fn new(line: u32, column: u32, end_line: u32, end_column: u32) -> Span {
+        Span { line, column, end_line, end_column }
+    }
+
+    /// The span of a single character
+    pub fn at(line: u32, column: u32) -> Span {
+        Span::new(line, column, line, column)
+    }
+
+    /// From the start of this span to the end of `other`
+    pub fn to(self, other: Span) -> Span {
+        Span { end_line: other.end_line, end_column: other.end_column, ..self }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
@@ -0,0 +1,481 @@
+use crate::lexer::lexer::SymbolKind;
+use super::rules::{Pattern, Rule, RULES, START};
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseAction {
+    Shift(usize),  // push the next token and go to the state
+    Reduce(usize), // replace the top of the stack by the result of the rule with this index in RULES
+    Accept,
+}
+
+/// Two actions wanted for the same state and terminal that precedence does not decide between
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    ShiftReduce,  // the terminal is shifted
+    ReduceReduce, // the rule listed first is reduced
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub state: usize,
+    pub terminal: Option<Pattern>,
+    pub rules: Vec<String>, // the rules that could be reduced
+    pub example: String,    // an input reaching the state, followed by the terminal
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ConflictKind::ShiftReduce => "shift/reduce",
+            ConflictKind::ReduceReduce => "reduce/reduce",
+        };
+        let terminal = self.terminal.map_or("end of input".to_string(), |t| t.to_string());
+        write!(f, "{} conflict in state {} on {} between {}", kind, self.state, terminal, self.rules.join(" and "))?;
+        match self.terminal {
+            Some(terminal) => write!(f, ", e.g. `{}` followed by `{}`", self.example, example_of(&terminal)),
+            None => write!(f, ", e.g. `{}` at the end of input", self.example),
+        }
+    }
+}
+
+/// LALR(1) table: what to do in each state for the next terminal (`None` at the end of input),
+/// and which state to go to once a rule has been reduced
+pub struct ParseTable {
+    actions: Vec<HashMap<Option<Pattern>, ParseAction>>,
+    gotos: Vec<HashMap<SymbolKind, usize>>,
+    reductions: Vec<(usize, SymbolKind)>, // symbols popped and the result, by rule
+    conflicts: Vec<Conflict>,
+}
+
+pub static TABLE: Lazy<ParseTable> = Lazy::new(|| ParseTable::new(&RULES, START));
+
+impl ParseTable {
+    pub fn new(rules: &[Rule], start: SymbolKind) -> ParseTable {
+        let grammar = Grammar::new(rules, start);
+        let (kernels, transitions) = grammar.states();
+
+        let mut table = ParseTable {
+            actions: vec![HashMap::new(); kernels.len()],
+            gotos: vec![HashMap::new(); kernels.len()],
+            reductions: rules.iter().map(|rule| (rule.pattern.len(), rule.result)).collect(),
+            conflicts: Vec::new(),
+        };
+        let mut conflicts = Vec::new();
+        for (state, kernel) in kernels.iter().enumerate() {
+            for (&(rule, dot), lookaheads) in &grammar.closure(kernel) {
+                match grammar.pattern(rule).get(dot) {
+                    None if rule == rules.len() => {
+                        table.actions[state].insert(None, ParseAction::Accept);
+                    }
+                    None => {
+                        for &lookahead in lookaheads {
+                            let terminal = grammar.terminal(lookahead);
+                            if let Some(conflict) = table.add_reduce(rules, state, terminal, rule) {
+                                conflicts.push(conflict);
+                            }
+                        }
+                    }
+                    Some(symbol) if grammar.is_terminal(symbol) => {
+                        let target = transitions[state][symbol];
+                        if let Some(conflict) = table.add_shift(rules, state, *symbol, target) {
+                            conflicts.push(conflict);
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (symbol, &target) in &transitions[state] {
+                if let Pattern::Kind(kind) = symbol
+                    && !grammar.is_terminal(symbol)
+                {
+                    table.gotos[state].insert(*kind, target);
+                }
+            }
+        }
+
+        if !conflicts.is_empty() {
+            let paths = shortest_paths(&transitions);
+            let expansions = grammar.shortest_expansions();
+            for (kind, state, terminal, conflicting) in conflicts {
+                let conflict = Conflict {
+                    kind,
+                    state,
+                    terminal,
+                    rules: conflicting.iter().map(|&rule| rules[rule].to_string()).collect(),
+                    example: example_input(&paths[state], &expansions),
+                };
+                if !table.conflicts.contains(&conflict) {
+                    table.conflicts.push(conflict);
+                }
+            }
+        }
+        table
+    }
+
+    pub fn action(&self, state: usize, next: Option<Pattern>) -> Option<ParseAction> {
+        self.actions[state].get(&next).copied()
+    }
+
+    pub fn goto(&self, state: usize, kind: SymbolKind) -> Option<usize> {
+        self.gotos[state].get(&kind).copied()
+    }
+
+    /// Terminals that can come next on the stack of states, `None` standing for the end of input.
+    /// The lookaheads of a merged LALR state also stand for other contexts, so only those still
+    /// shifted (or accepted) after the reductions they lead to are kept
+    pub fn expected(&self, states: &[usize]) -> Vec<Option<Pattern>> {
+        let top = *states.last().unwrap();
+        let mut expected: Vec<Option<Pattern>> = self.actions[top]
+            .keys()
+            .filter(|terminal| **terminal != Some(Pattern::Kind(SymbolKind::Error)))
+            .filter(|terminal| self.leads_to_shift(states, **terminal))
+            .copied()
+            .collect();
+        expected.sort_by_key(|terminal| terminal.map(|t| t.to_string()));
+        expected
+    }
+
+    /// Runs the reductions the terminal causes on a copy of the stack, true when it is then shifted
+    fn leads_to_shift(&self, states: &[usize], terminal: Option<Pattern>) -> bool {
+        let mut states = states.to_vec();
+        loop {
+            match self.action(*states.last().unwrap(), terminal) {
+                Some(ParseAction::Shift(_) | ParseAction::Accept) => return true,
+                Some(ParseAction::Reduce(rule)) => {
+                    let (length, result) = self.reductions[rule];
+                    states.truncate(states.len() - length);
+                    match self.goto(*states.last().unwrap(), result) {
+                        Some(target) => states.push(target),
+                        None => return false,
+                    }
+                }
+                None => return false,
+            }
+        }
+    }
+
+    /// Conflicts found while building the table, empty for an unambiguous grammar
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    fn add_shift(&mut self, rules: &[Rule], state: usize, terminal: Pattern, target: usize) -> Option<PendingConflict> {
+        let mut conflict = None;
+        if let Some(ParseAction::Reduce(rule)) = self.actions[state].get(&Some(terminal)) {
+            match shifts_over(&rules[*rule], Some(terminal)) {
+                Some(false) => return None,
+                Some(true) => {}
+                None => conflict = Some((ConflictKind::ShiftReduce, state, Some(terminal), vec![*rule])),
+            }
+        }
+        self.actions[state].insert(Some(terminal), ParseAction::Shift(target));
+        conflict
+    }
+
+    fn add_reduce(&mut self, rules: &[Rule], state: usize, terminal: Option<Pattern>, rule: usize) -> Option<PendingConflict> {
+        match self.actions[state].get(&terminal) {
+            Some(ParseAction::Accept) => None,
+            Some(ParseAction::Shift(_)) => match shifts_over(&rules[rule], terminal) {
+                Some(true) => None,
+                Some(false) => {
+                    self.actions[state].insert(terminal, ParseAction::Reduce(rule));
+                    None
+                }
+                None => Some((ConflictKind::ShiftReduce, state, terminal, vec![rule])),
+            },
+            Some(ParseAction::Reduce(other)) => {
+                let (first, second) = (rule.min(*other), rule.max(*other));
+                self.actions[state].insert(terminal, ParseAction::Reduce(first));
+                (first != second).then(|| (ConflictKind::ReduceReduce, state, terminal, vec![first, second]))
+            }
+            None => {
+                self.actions[state].insert(terminal, ParseAction::Reduce(rule));
+                None
+            }
+        }
+    }
+}
+
+/// A conflict before its rules and example are rendered
+type PendingConflict = (ConflictKind, usize, Option<Pattern>, Vec<usize>);
+
+/// Whether the next terminal is shifted rather than the rule reduced, `None` when either has no precedence
+/// (the terminal is then shifted). An equal precedence reduces so that operators are left associative
+fn shifts_over(rule: &Rule, terminal: Option<Pattern>) -> Option<bool> {
+    let rule = rule.precedence?;
+    let terminal = terminal?.precedence()?;
+    Some(terminal > rule)
+}
+
+/// The shortest symbol sequence leading from the first state to each state
+fn shortest_paths(transitions: &[HashMap<Pattern, usize>]) -> Vec<Vec<Pattern>> {
+    let mut paths: Vec<Option<Vec<Pattern>>> = vec![None; transitions.len()];
+    paths[0] = Some(Vec::new());
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(state) = queue.pop_front() {
+        let path = paths[state].clone().unwrap();
+        // sorted so that the examples are the same on every run
+        let mut successors: Vec<(&Pattern, &usize)> = transitions[state].iter().collect();
+        successors.sort_by_key(|(symbol, target)| (symbol.to_string(), **target));
+        for (symbol, &target) in successors {
+            if paths[target].is_none() {
+                let mut next = path.clone();
+                next.push(*symbol);
+                paths[target] = Some(next);
+                queue.push_back(target);
+            }
+        }
+    }
+    paths.into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// Source text for a symbol sequence, every nonterminal replaced by its shortest expansion
+fn example_input(path: &[Pattern], expansions: &HashMap<SymbolKind, Vec<Pattern>>) -> String {
+    let mut words = Vec::new();
+    for symbol in path {
+        match symbol {
+            Pattern::Kind(kind) if expansions.contains_key(kind) => {
+                words.extend(expansions[kind].iter().map(example_of));
+            }
+            terminal => words.push(example_of(terminal)),
+        }
+    }
+    words.join(" ")
+}
+
+/// A token matching a terminal
+fn example_of(terminal: &Pattern) -> String {
+    match terminal {
+        Pattern::Kind(SymbolKind::Identifier) => "x".to_string(),
+        Pattern::Kind(SymbolKind::Number) => "1".to_string(),
+        Pattern::Kind(SymbolKind::String) => "\"s\"".to_string(),
+        Pattern::Kind(SymbolKind::Char) => "'c'".to_string(),
+        Pattern::Kind(SymbolKind::Bool) => "true".to_string(),
+        Pattern::Kind(SymbolKind::TypeIdentifier) => "int".to_string(),
+        Pattern::Kind(kind) => format!("{:?}", kind),
+        Pattern::Punctuation(p) => p.as_str().to_string(),
+        Pattern::Operator(op) => op.as_str().to_string(),
+        Pattern::Keyword(k) => k.as_str().to_string(),
+    }
+}
+
+/// A rule with the position reached in its pattern
+type Item = (usize, usize);
+
+/// Terminals that may follow an item, as indexes into `Grammar::terminals` (`END` for the end of input)
+type Lookaheads = BTreeSet<usize>;
+
+type Kernel = BTreeMap<Item, Lookaheads>;
+
+const END: usize = usize::MAX;
+
+/// The rules with the sets needed to build the table, the rule past the last one is `start' -> start`
+struct Grammar<'a> {
+    rules: &'a [Rule],
+    start: [Pattern; 1],
+    nonterminals: HashSet<SymbolKind>,
+    terminals: Vec<Pattern>,
+    first: HashMap<SymbolKind, Lookaheads>,
+    nullable: HashSet<SymbolKind>,
+}
+
+impl<'a> Grammar<'a> {
+    fn new(rules: &'a [Rule], start: SymbolKind) -> Grammar<'a> {
+        let nonterminals: HashSet<SymbolKind> = rules.iter().map(|rule| rule.result).collect();
+        let mut terminals = Vec::new();
+        for pattern in rules.iter().flat_map(|rule| &rule.pattern) {
+            let is_terminal = !matches!(pattern, Pattern::Kind(kind) if nonterminals.contains(kind));
+            if is_terminal && !terminals.contains(pattern) {
+                terminals.push(*pattern);
+            }
+        }
+
+        let mut grammar = Grammar {
+            rules,
+            start: [Pattern::Kind(start)],
+            nonterminals,
+            terminals,
+            first: HashMap::new(),
+            nullable: HashSet::new(),
+        };
+        grammar.compute_first();
+        grammar
+    }
+
+    fn pattern(&self, rule: usize) -> &[Pattern] {
+        match self.rules.get(rule) {
+            Some(rule) => &rule.pattern,
+            None => &self.start,
+        }
+    }
+
+    fn is_terminal(&self, pattern: &Pattern) -> bool {
+        !matches!(pattern, Pattern::Kind(kind) if self.nonterminals.contains(kind))
+    }
+
+    fn terminal(&self, index: usize) -> Option<Pattern> {
+        self.terminals.get(index).copied()
+    }
+
+    fn terminal_index(&self, pattern: &Pattern) -> usize {
+        self.terminals.iter().position(|terminal| terminal == pattern).unwrap()
+    }
+
+    /// FIRST sets and nullable nonterminals, grown until nothing changes
+    fn compute_first(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.rules {
+                let (first, nullable) = self.first_of(&rule.pattern);
+                let entry = self.first.entry(rule.result).or_default();
+                let before = entry.len();
+                entry.extend(first);
+                changed |= entry.len() != before;
+                if nullable {
+                    changed |= self.nullable.insert(rule.result);
+                }
+            }
+        }
+    }
+
+    /// Terminals that can start the sequence, and whether it can be empty
+    fn first_of(&self, sequence: &[Pattern]) -> (Lookaheads, bool) {
+        let mut first = Lookaheads::new();
+        for pattern in sequence {
+            match pattern {
+                Pattern::Kind(kind) if self.nonterminals.contains(kind) => {
+                    first.extend(self.first.get(kind).into_iter().flatten());
+                    if !self.nullable.contains(kind) {
+                        return (first, false);
+                    }
+                }
+                terminal => {
+                    first.insert(self.terminal_index(terminal));
+                    return (first, false);
+                }
+            }
+        }
+        (first, true)
+    }
+
+    /// The shortest terminal sequence each nonterminal derives, grown until nothing changes
+    fn shortest_expansions(&self) -> HashMap<SymbolKind, Vec<Pattern>> {
+        let mut expansions: HashMap<SymbolKind, Vec<Pattern>> = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.rules {
+                let mut expansion = Vec::new();
+                let complete = rule.pattern.iter().all(|pattern| match pattern {
+                    Pattern::Kind(kind) if self.nonterminals.contains(kind) => match expansions.get(kind) {
+                        Some(inner) => {
+                            expansion.extend(inner.iter().copied());
+                            true
+                        }
+                        None => false,
+                    },
+                    terminal => {
+                        expansion.push(*terminal);
+                        true
+                    }
+                });
+                let shorter = expansions.get(&rule.result).is_none_or(|existing| expansion.len() < existing.len());
+                if complete && shorter {
+                    expansions.insert(rule.result, expansion);
+                    changed = true;
+                }
+            }
+        }
+        expansions
+    }
+
+    /// Adds the items of every rule that can start at a nonterminal after the dot
+    fn closure(&self, kernel: &Kernel) -> Kernel {
+        let mut items = kernel.clone();
+        let mut pending: Vec<Item> = items.keys().copied().collect();
+
+        while let Some((rule, dot)) = pending.pop() {
+            let pattern = self.pattern(rule);
+            let Some(Pattern::Kind(kind)) = pattern.get(dot) else {
+                continue;
+            };
+            if !self.nonterminals.contains(kind) {
+                continue;
+            }
+
+            let (mut lookaheads, nullable) = self.first_of(&pattern[dot + 1..]);
+            if nullable {
+                lookaheads.extend(&items[&(rule, dot)]);
+            }
+            for (index, _) in self.rules.iter().enumerate().filter(|(_, r)| r.result == *kind) {
+                let entry = items.entry((index, 0)).or_default();
+                let before = entry.len();
+                entry.extend(&lookaheads);
+                if entry.len() != before {
+                    pending.push((index, 0));
+                }
+            }
+        }
+        items
+    }
+
+    /// The kernels of all states and their transitions, states with the same items share their lookaheads
+    fn states(&self) -> (Vec<Kernel>, Vec<HashMap<Pattern, usize>>) {
+        let mut kernels = vec![Kernel::from([((self.rules.len(), 0), Lookaheads::from([END]))])];
+        let mut transitions: Vec<HashMap<Pattern, usize>> = vec![HashMap::new()];
+        let mut by_items: HashMap<Vec<Item>, usize> = HashMap::from([(vec![(self.rules.len(), 0)], 0)]);
+        let mut pending = vec![0];
+
+        while let Some(state) = pending.pop() {
+            // grouped in item order so that states are numbered the same way on every run
+            let mut successors: Vec<(Pattern, Kernel)> = Vec::new();
+            for ((rule, dot), lookaheads) in self.closure(&kernels[state]) {
+                let Some(symbol) = self.pattern(rule).get(dot) else {
+                    continue;
+                };
+                let position = match successors.iter().position(|(s, _)| s == symbol) {
+                    Some(position) => position,
+                    None => {
+                        successors.push((*symbol, Kernel::new()));
+                        successors.len() - 1
+                    }
+                };
+                successors[position].1.entry((rule, dot + 1)).or_default().extend(lookaheads);
+            }
+
+            for (symbol, kernel) in successors {
+                let items: Vec<Item> = kernel.keys().copied().collect();
+                let target = match by_items.get(&items) {
+                    Some(&target) => {
+                        let mut changed = false;
+                        for (item, lookaheads) in kernel {
+                            let entry = kernels[target].get_mut(&item).unwrap();
+                            let before = entry.len();
+                            entry.extend(lookaheads);
+                            changed |= entry.len() != before;
+                        }
+                        if changed && !pending.contains(&target) {
+                            pending.push(target);
+                        }
+                        target
+                    }
+                    None => {
+                        kernels.push(kernel);
+                        transitions.push(HashMap::new());
+                        by_items.insert(items, kernels.len() - 1);
+                        pending.push(kernels.len() - 1);
+                        kernels.len() - 1
+                    }
+                };
+                transitions[state].insert(symbol, target);
+            }
+        }
+        (kernels, transitions)
+    }
+}
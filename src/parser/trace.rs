@@ -0,0 +1,47 @@
+use crate::helper::visualize_ast::write_ast;
+use super::ast::SymbolNode;
+use super::rules::{Pattern, Rule};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TraceLevel {
+    Off,
+    Reductions, // one line per reduced rule
+    Stack,      // every shift and reduction, each followed by the whole stack
+}
+
+/// Where the parser describes what it does, nothing is written unless a level is chosen
+pub struct Trace<'a> {
+    level: TraceLevel,
+    sink: Box<dyn Write + 'a>,
+}
+
+impl<'a> Trace<'a> {
+    pub fn off() -> Trace<'a> {
+        Trace { level: TraceLevel::Off, sink: Box::new(io::sink()) }
+    }
+
+    pub fn new(level: TraceLevel, sink: Box<dyn Write + 'a>) -> Trace<'a> {
+        Trace { level, sink }
+    }
+
+    pub fn shift(&mut self, line: u32, symbol: &SymbolNode, stack: &[SymbolNode]) {
+        if self.level >= TraceLevel::Stack {
+            self.write(format!("line {}: shift {}", line, Pattern::of(symbol).grammar_name()), stack);
+        }
+    }
+
+    pub fn reduction(&mut self, line: u32, rule: &Rule, stack: &[SymbolNode]) {
+        if self.level >= TraceLevel::Reductions {
+            self.write(format!("line {}: reduce {}", line, rule), stack);
+        }
+    }
+
+    // the trace is only a debugging aid, a failing sink does not stop the parse
+    fn write(&mut self, event: String, stack: &[SymbolNode]) {
+        let _ = writeln!(self.sink, "{}", event);
+        if self.level >= TraceLevel::Stack {
+            let _ = write_ast(&mut self.sink, stack).and_then(|_| writeln!(self.sink));
+        }
+    }
+}
@@ -1,23 +1,160 @@
+use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum OperatorType {
     Add,
     Sub,
     Mul,
     Div,
     Equal,
+    AddressOf,
+    // only written as part of a compound assignment (`%=`, `&=`, ...)
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    // x += 1;
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    AndAssign,
+    OrAssign,
+    XorAssign,
+    ShiftLeftAssign,
+    ShiftRightAssign,
+    // ++x, x--
+    Increment,
+    Decrement,
 }
 impl OperatorType {
-    pub fn from_char(char: char) -> Option<OperatorType> {
-        match char {
-            '+' => Some(OperatorType::Add),
-            '-' => Some(OperatorType::Sub),
-            '*' => Some(OperatorType::Mul),
-            '/' => Some(OperatorType::Div),
-            '=' => Some(OperatorType::Equal),
+    const ALL: [OperatorType; 24] = [
+        OperatorType::Add,
+        OperatorType::Sub,
+        OperatorType::Mul,
+        OperatorType::Div,
+        OperatorType::Equal,
+        OperatorType::AddressOf,
+        OperatorType::Mod,
+        OperatorType::BitAnd,
+        OperatorType::BitOr,
+        OperatorType::BitXor,
+        OperatorType::ShiftLeft,
+        OperatorType::ShiftRight,
+        OperatorType::AddAssign,
+        OperatorType::SubAssign,
+        OperatorType::MulAssign,
+        OperatorType::DivAssign,
+        OperatorType::ModAssign,
+        OperatorType::AndAssign,
+        OperatorType::OrAssign,
+        OperatorType::XorAssign,
+        OperatorType::ShiftLeftAssign,
+        OperatorType::ShiftRightAssign,
+        OperatorType::Increment,
+        OperatorType::Decrement,
+    ];
+
+    /// The operator written `s`, a lone `&` being the address-of operator
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<OperatorType> {
+        OperatorType::ALL
+            .into_iter()
+            .filter(|op| *op != OperatorType::BitAnd)
+            .find(|op| op.as_str() == s)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperatorType::Add => "+",
+            OperatorType::Sub => "-",
+            OperatorType::Mul => "*",
+            OperatorType::Div => "/",
+            OperatorType::Equal => "=",
+            OperatorType::AddressOf | OperatorType::BitAnd => "&",
+            OperatorType::Mod => "%",
+            OperatorType::BitOr => "|",
+            OperatorType::BitXor => "^",
+            OperatorType::ShiftLeft => "<<",
+            OperatorType::ShiftRight => ">>",
+            OperatorType::AddAssign => "+=",
+            OperatorType::SubAssign => "-=",
+            OperatorType::MulAssign => "*=",
+            OperatorType::DivAssign => "/=",
+            OperatorType::ModAssign => "%=",
+            OperatorType::AndAssign => "&=",
+            OperatorType::OrAssign => "|=",
+            OperatorType::XorAssign => "^=",
+            OperatorType::ShiftLeftAssign => "<<=",
+            OperatorType::ShiftRightAssign => ">>=",
+            OperatorType::Increment => "++",
+            OperatorType::Decrement => "--",
+        }
+    }
+
+    /// Binding strength of a binary operator, higher binds tighter (`? :` is below them all, at 1)
+    pub fn precedence(&self) -> u8 {
+        match self {
+            OperatorType::Mul | OperatorType::Div | OperatorType::Mod => 3,
+            OperatorType::Add | OperatorType::Sub => 2,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Keyword {
+    Return,
+    Struct,
+    Switch,
+    Case,
+    Default,
+    Break,
+    Const,
+    Enum,
+    Typedef,
+    Import,
+    Pub,
+}
+
+impl Keyword {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Keyword> {
+        match s {
+            "return" => Some(Keyword::Return),
+            "struct" => Some(Keyword::Struct),
+            "switch" => Some(Keyword::Switch),
+            "case" => Some(Keyword::Case),
+            "default" => Some(Keyword::Default),
+            "break" => Some(Keyword::Break),
+            "const" => Some(Keyword::Const),
+            "enum" => Some(Keyword::Enum),
+            "typedef" => Some(Keyword::Typedef),
+            "import" => Some(Keyword::Import),
+            "pub" => Some(Keyword::Pub),
             _ => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Return => "return",
+            Keyword::Struct => "struct",
+            Keyword::Switch => "switch",
+            Keyword::Case => "case",
+            Keyword::Default => "default",
+            Keyword::Break => "break",
+            Keyword::Const => "const",
+            Keyword::Enum => "enum",
+            Keyword::Typedef => "typedef",
+            Keyword::Import => "import",
+            Keyword::Pub => "pub",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -25,33 +162,168 @@ pub enum Type {
     Integer,
     String,
     Bool,
-    Char
+    Char,
+    Void,
+    Array(Box<Type>, u32), // element type and length
+    Struct(String),        // layout is looked up by name in the StructTable
+    Enum(String),          // stored like an int, its variants are constants of this type
+    Pointer(Box<Type>),
 }
 
 impl Type {
-    pub fn from_str(s: &str) -> Option<Type> {
-        match s {
-            "int" => Some(Type::Integer),
-            "string" => Some(Type::String),
-            "bool" => Some(Type::Bool),
-            "char" => Some(Type::Char),
-            _ => None,
-        }
-    }
-
-    pub fn to_size_asm(&self) -> i32 {
+    pub fn to_size_asm(&self, structs: &StructTable) -> i32 {
         match self {
             Type::Integer => 4,
             Type::Bool => 1,
             Type::Char => 1,
             Type::String => 100,
+            Type::Void => 0,
+            Type::Array(element, length) => element.to_size_asm(structs) * *length as i32,
+            Type::Struct(name) => structs.get(name).map_or(0, |layout| layout.size),
+            Type::Enum(_) => 4,
+            Type::Pointer(_) => 8,
+        }
+    }
+
+    pub fn alignment(&self, structs: &StructTable) -> i32 {
+        match self {
+            Type::Integer | Type::Enum(_) => 4,
+            Type::Array(element, _) => element.alignment(structs),
+            Type::Struct(name) => structs.get(name).map_or(1, |layout| layout.alignment),
+            Type::Pointer(_) => 8,
+            _ => 1,
+        }
+    }
+
+    /// The type both arms of a conditional have, a `char` arm is widened to an `int` one
+    pub fn common(a: &Type, b: &Type) -> Option<Type> {
+        match (a, b) {
+            (Type::Void, _) | (_, Type::Void) => None,
+            _ if a == b => Some(a.clone()),
+            (Type::Char, Type::Integer) | (Type::Integer, Type::Char) => Some(Type::Integer),
+            _ => None,
+        }
+    }
+
+    /// Adds a dimension to a declared type, `int a[2][3]` is an array of 2 arrays of 3 ints
+    pub fn with_dimension(self, length: u32) -> Type {
+        match self {
+            Type::Array(element, outer) => Type::Array(Box::new(element.with_dimension(length)), outer),
+            element => Type::Array(Box::new(element), length),
+        }
+    }
+
+    /// The declared type without its `[length]` suffixes, `int a[2], b;` declares `b` an `int`
+    pub fn without_dimensions(self) -> Type {
+        match self {
+            Type::Array(element, _) => element.without_dimensions(),
+            element => element,
+        }
+    }
+
+}
+
+/// The type as written in source: `int`, `struct Point*`, an array as `int[4]`
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Integer => write!(f, "int"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Void => write!(f, "void"),
+            Type::Struct(name) => write!(f, "struct {}", name),
+            Type::Enum(name) => write!(f, "enum {}", name),
+            Type::Pointer(target) => write!(f, "{}*", target),
+            Type::Array(element, length) => write!(f, "{}[{}]", element, length),
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Field {
+    pub name: String,
+    pub typ: Type,
+    pub offset: i32,
+}
+
+/// Memory layout of a struct, fields are placed in order at their natural alignment like in C
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructLayout {
+    pub fields: Vec<Field>,
+    pub size: i32,
+    pub alignment: i32,
+}
+
+impl StructLayout {
+    pub fn new(fields: &[(String, Type)], structs: &StructTable) -> StructLayout {
+        let mut offset = 0;
+        let mut alignment = 1;
+        let mut laid_out = Vec::new();
+
+        for (name, typ) in fields {
+            let field_alignment = typ.alignment(structs);
+            offset = align_to(offset, field_alignment);
+            laid_out.push(Field { name: name.clone(), typ: typ.clone(), offset });
+            offset += typ.to_size_asm(structs);
+            alignment = alignment.max(field_alignment);
         }
+
+        StructLayout { fields: laid_out, size: align_to(offset, alignment), alignment }
     }
 
+    pub fn get_field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
 }
 
+fn align_to(offset: i32, alignment: i32) -> i32 {
+    (offset + alignment - 1) / alignment * alignment
+}
 
-#[derive(Debug, PartialEq, Eq, Hash,)]
+/// Declared structs by name
+pub type StructTable = HashMap<String, StructLayout>;
+
+/// Names that stand for a type: the built-in ones and the aliases declared with `typedef`
+pub struct TypeTable {
+    names: HashMap<String, Type>,
+}
+
+impl TypeTable {
+    pub fn new() -> TypeTable {
+        let builtins = [
+            ("int", Type::Integer),
+            ("string", Type::String),
+            ("bool", Type::Bool),
+            ("char", Type::Char),
+            ("void", Type::Void),
+        ];
+        TypeTable { names: builtins.into_iter().map(|(name, typ)| (name.to_string(), typ)).collect() }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        self.names.get(name)
+    }
+
+    /// Makes `name` stand for `typ`, false when it already names a type
+    pub fn add_alias(&mut self, name: &str, typ: Type) -> bool {
+        if self.names.contains_key(name) {
+            return false;
+        }
+        self.names.insert(name.to_string(), typ);
+        true
+    }
+}
+
+impl Default for TypeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Punctuation {
     OpenBracket,
     CloseBracket,
@@ -60,6 +332,10 @@ pub enum Punctuation {
     OpenParen,
     CloseParen,
     Semicolon,
+    Comma,
+    Dot,
+    Question,
+    Colon,
 }
 
 impl Punctuation {
@@ -72,7 +348,27 @@ impl Punctuation {
             '[' => Some(Punctuation::OpenBracket),
             ']' => Some(Punctuation::CloseBracket),
             ';' => Some(Punctuation::Semicolon),
+            ',' => Some(Punctuation::Comma),
+            '.' => Some(Punctuation::Dot),
+            '?' => Some(Punctuation::Question),
+            ':' => Some(Punctuation::Colon),
             _ => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Punctuation::OpenBrace => "{",
+            Punctuation::CloseBrace => "}",
+            Punctuation::OpenParen => "(",
+            Punctuation::CloseParen => ")",
+            Punctuation::OpenBracket => "[",
+            Punctuation::CloseBracket => "]",
+            Punctuation::Semicolon => ";",
+            Punctuation::Comma => ",",
+            Punctuation::Dot => ".",
+            Punctuation::Question => "?",
+            Punctuation::Colon => ":",
+        }
+    }
 }
\ No newline at end of file
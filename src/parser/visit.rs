@@ -0,0 +1,307 @@
+use super::program::{Case, Declaration, Expr, ExprKind, Field, Function, Item, ItemKind, Param, Program, Stmt, StmtKind, Variant};
+use super::types::Type;
+
+/*
+structure:
+Traversals of the `Program` tree, for the analyses and rewrites that look at some kinds of nodes and
+only need to reach the others. Every `visit_*` method walks into the children of its node by default,
+an implementation overrides the methods of the nodes it is about and calls the matching `walk_*`
+function where it still wants the children visited. `Visitor` reads the tree, `VisitorMut` may change
+it in place. Children are visited in the order they are written, types before the names they declare.
+ */
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_item(&mut self, item: &Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        walk_declaration(self, declaration);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        walk_function(self, function);
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_field(&mut self, field: &Field) {
+        walk_field(self, field);
+    }
+
+    fn visit_variant(&mut self, variant: &Variant) {
+        walk_variant(self, variant);
+    }
+
+    fn visit_stmt(&mut self, statement: &Stmt) {
+        walk_stmt(self, statement);
+    }
+
+    fn visit_case(&mut self, case: &Case) {
+        walk_case(self, case);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_type(&mut self, typ: &Type) {
+        walk_type(self, typ);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    program.items.iter().for_each(|item| visitor.visit_item(item));
+}
+
+pub fn walk_item<V: Visitor + ?Sized>(visitor: &mut V, item: &Item) {
+    match &item.kind {
+        ItemKind::Global(declaration) => visitor.visit_declaration(declaration),
+        ItemKind::Function(function) => visitor.visit_function(function),
+        ItemKind::Struct { fields, .. } => fields.iter().for_each(|field| visitor.visit_field(field)),
+        ItemKind::Enum { variants, .. } => variants.iter().for_each(|variant| visitor.visit_variant(variant)),
+        ItemKind::TypeAlias { typ, .. } => visitor.visit_type(typ),
+        ItemKind::Public(item) => visitor.visit_item(item),
+        ItemKind::Statement(statement) => visitor.visit_stmt(statement),
+        ItemKind::Import { .. } | ItemKind::Error => {}
+    }
+}
+
+pub fn walk_declaration<V: Visitor + ?Sized>(visitor: &mut V, declaration: &Declaration) {
+    visitor.visit_type(&declaration.typ);
+    if let Some(value) = &declaration.value {
+        visitor.visit_expr(value);
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, function: &Function) {
+    visitor.visit_type(&function.return_type);
+    function.params.iter().for_each(|param| visitor.visit_param(param));
+    function.body.iter().for_each(|statement| visitor.visit_stmt(statement));
+}
+
+pub fn walk_param<V: Visitor + ?Sized>(visitor: &mut V, param: &Param) {
+    visitor.visit_type(&param.typ);
+}
+
+pub fn walk_field<V: Visitor + ?Sized>(visitor: &mut V, field: &Field) {
+    visitor.visit_type(&field.typ);
+}
+
+pub fn walk_variant<V: Visitor + ?Sized>(visitor: &mut V, variant: &Variant) {
+    if let Some(value) = &variant.value {
+        visitor.visit_expr(value);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, statement: &Stmt) {
+    match &statement.kind {
+        StmtKind::Declaration(declaration) => visitor.visit_declaration(declaration),
+        StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => visitor.visit_expr(expr),
+        StmtKind::Switch { value, cases } => {
+            visitor.visit_expr(value);
+            cases.iter().for_each(|case| visitor.visit_case(case));
+        }
+        StmtKind::Return(None) | StmtKind::Break | StmtKind::Error => {}
+    }
+}
+
+pub fn walk_case<V: Visitor + ?Sized>(visitor: &mut V, case: &Case) {
+    if let Some(value) = &case.value {
+        visitor.visit_expr(value);
+    }
+    case.body.iter().for_each(|statement| visitor.visit_stmt(statement));
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match &expr.kind {
+        ExprKind::Binary { left, right, .. } | ExprKind::Index { array: left, index: right } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        ExprKind::Unary { operand, .. } | ExprKind::Update { operand, .. } => visitor.visit_expr(operand),
+        ExprKind::Conditional { condition, if_true, if_false } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(if_true);
+            visitor.visit_expr(if_false);
+        }
+        ExprKind::Cast { typ, operand } => {
+            visitor.visit_type(typ);
+            visitor.visit_expr(operand);
+        }
+        ExprKind::Call { args, .. } => args.iter().for_each(|arg| visitor.visit_expr(arg)),
+        ExprKind::Member { object, .. } => visitor.visit_expr(object),
+        ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Char(_)
+        | ExprKind::Identifier(_)
+        | ExprKind::Error => {}
+    }
+}
+
+/// The element type of an array or pointer is visited as a type of its own
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, typ: &Type) {
+    match typ {
+        Type::Array(element, _) | Type::Pointer(element) => visitor.visit_type(element),
+        Type::Integer | Type::String | Type::Bool | Type::Char | Type::Void | Type::Struct(_) | Type::Enum(_) => {}
+    }
+}
+
+/// Like `Visitor`, with every node handed out mutably so that it can be rewritten where it is
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        walk_item_mut(self, item);
+    }
+
+    fn visit_declaration_mut(&mut self, declaration: &mut Declaration) {
+        walk_declaration_mut(self, declaration);
+    }
+
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        walk_function_mut(self, function);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        walk_param_mut(self, param);
+    }
+
+    fn visit_field_mut(&mut self, field: &mut Field) {
+        walk_field_mut(self, field);
+    }
+
+    fn visit_variant_mut(&mut self, variant: &mut Variant) {
+        walk_variant_mut(self, variant);
+    }
+
+    fn visit_stmt_mut(&mut self, statement: &mut Stmt) {
+        walk_stmt_mut(self, statement);
+    }
+
+    fn visit_case_mut(&mut self, case: &mut Case) {
+        walk_case_mut(self, case);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_type_mut(&mut self, typ: &mut Type) {
+        walk_type_mut(self, typ);
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    program.items.iter_mut().for_each(|item| visitor.visit_item_mut(item));
+}
+
+pub fn walk_item_mut<V: VisitorMut + ?Sized>(visitor: &mut V, item: &mut Item) {
+    match &mut item.kind {
+        ItemKind::Global(declaration) => visitor.visit_declaration_mut(declaration),
+        ItemKind::Function(function) => visitor.visit_function_mut(function),
+        ItemKind::Struct { fields, .. } => fields.iter_mut().for_each(|field| visitor.visit_field_mut(field)),
+        ItemKind::Enum { variants, .. } => variants.iter_mut().for_each(|variant| visitor.visit_variant_mut(variant)),
+        ItemKind::TypeAlias { typ, .. } => visitor.visit_type_mut(typ),
+        ItemKind::Public(item) => visitor.visit_item_mut(item),
+        ItemKind::Statement(statement) => visitor.visit_stmt_mut(statement),
+        ItemKind::Import { .. } | ItemKind::Error => {}
+    }
+}
+
+pub fn walk_declaration_mut<V: VisitorMut + ?Sized>(visitor: &mut V, declaration: &mut Declaration) {
+    visitor.visit_type_mut(&mut declaration.typ);
+    if let Some(value) = &mut declaration.value {
+        visitor.visit_expr_mut(value);
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut Function) {
+    visitor.visit_type_mut(&mut function.return_type);
+    function.params.iter_mut().for_each(|param| visitor.visit_param_mut(param));
+    function.body.iter_mut().for_each(|statement| visitor.visit_stmt_mut(statement));
+}
+
+pub fn walk_param_mut<V: VisitorMut + ?Sized>(visitor: &mut V, param: &mut Param) {
+    visitor.visit_type_mut(&mut param.typ);
+}
+
+pub fn walk_field_mut<V: VisitorMut + ?Sized>(visitor: &mut V, field: &mut Field) {
+    visitor.visit_type_mut(&mut field.typ);
+}
+
+pub fn walk_variant_mut<V: VisitorMut + ?Sized>(visitor: &mut V, variant: &mut Variant) {
+    if let Some(value) = &mut variant.value {
+        visitor.visit_expr_mut(value);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Stmt) {
+    match &mut statement.kind {
+        StmtKind::Declaration(declaration) => visitor.visit_declaration_mut(declaration),
+        StmtKind::Assign { target, value } | StmtKind::CompoundAssign { target, value, .. } => {
+            visitor.visit_expr_mut(target);
+            visitor.visit_expr_mut(value);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(Some(expr)) => visitor.visit_expr_mut(expr),
+        StmtKind::Switch { value, cases } => {
+            visitor.visit_expr_mut(value);
+            cases.iter_mut().for_each(|case| visitor.visit_case_mut(case));
+        }
+        StmtKind::Return(None) | StmtKind::Break | StmtKind::Error => {}
+    }
+}
+
+pub fn walk_case_mut<V: VisitorMut + ?Sized>(visitor: &mut V, case: &mut Case) {
+    if let Some(value) = &mut case.value {
+        visitor.visit_expr_mut(value);
+    }
+    case.body.iter_mut().for_each(|statement| visitor.visit_stmt_mut(statement));
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match &mut expr.kind {
+        ExprKind::Binary { left, right, .. } | ExprKind::Index { array: left, index: right } => {
+            visitor.visit_expr_mut(left);
+            visitor.visit_expr_mut(right);
+        }
+        ExprKind::Unary { operand, .. } | ExprKind::Update { operand, .. } => visitor.visit_expr_mut(operand),
+        ExprKind::Conditional { condition, if_true, if_false } => {
+            visitor.visit_expr_mut(condition);
+            visitor.visit_expr_mut(if_true);
+            visitor.visit_expr_mut(if_false);
+        }
+        ExprKind::Cast { typ, operand } => {
+            visitor.visit_type_mut(typ);
+            visitor.visit_expr_mut(operand);
+        }
+        ExprKind::Call { args, .. } => args.iter_mut().for_each(|arg| visitor.visit_expr_mut(arg)),
+        ExprKind::Member { object, .. } => visitor.visit_expr_mut(object),
+        ExprKind::Number(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Char(_)
+        | ExprKind::Identifier(_)
+        | ExprKind::Error => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut + ?Sized>(visitor: &mut V, typ: &mut Type) {
+    match typ {
+        Type::Array(element, _) | Type::Pointer(element) => visitor.visit_type_mut(element),
+        Type::Integer | Type::String | Type::Bool | Type::Char | Type::Void | Type::Struct(_) | Type::Enum(_) => {}
+    }
+}
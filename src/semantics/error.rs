@@ -0,0 +1,22 @@
+use std::fmt;
+
+use crate::parser::span::Span;
+
+/// Why a program that parses is not a valid one, found at the span of the node it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl SemanticError {
+    pub fn new(span: Span, message: impl Into<String>) -> SemanticError {
+        SemanticError { span, message: message.into() }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span, self.message)
+    }
+}
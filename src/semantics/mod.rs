@@ -1 +1,2 @@
-pub mod semantics;
\ No newline at end of file
+pub mod error;
+pub mod semantics;
@@ -1,16 +1,60 @@
-use crate::{error, info};
-use crate::parser::ast::{ASTNode, SymbolNode};
-use crate::parser::types::Type;
+use crate::info;
+use super::error::SemanticError;
+use crate::parser::program::{
+    AssignOp, BinOp, Case, Declaration, Expr, ExprKind, Field, Function, Item, ItemKind, Program, Stmt, StmtKind, UnaryOp, Variant,
+};
+use crate::parser::span::Span;
+use crate::parser::types::{StructLayout, StructTable, Type, TypeTable};
 use crate::parser::types;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Conversions a cast may make between different scalar types, a pointer may also be cast to any other pointer
+/// and an enum to and from an int
+const CASTS: [(Type, Type); 6] = [
+    (Type::Char, Type::Integer),  // sign extended
+    (Type::Bool, Type::Integer),  // zero extended
+    (Type::Integer, Type::Char),  // truncated
+    (Type::Bool, Type::Char),     // zero extended
+    (Type::Integer, Type::Bool),  // whether it is not zero
+    (Type::Char, Type::Bool),     // whether it is not zero
+];
+
+/// The type of an operand in arithmetic or where an int is expected, an enum value counts as its number
+fn widened(typ: Type) -> Type {
+    match typ {
+        Type::Enum(_) => Type::Integer,
+        typ => typ,
+    }
+}
+
+/// Whether the statement ends every way through it with a return
+fn returns(statement: &Stmt) -> bool {
+    match &statement.kind {
+        StmtKind::Return(_) => true,
+        StmtKind::Switch { cases, .. } => switch_returns(cases),
+        _ => false,
+    }
+}
+
+/// A switch returns when it has a default and every case returns before a `break` leaves the switch,
+/// an empty case by falling through to the next one
+fn switch_returns(cases: &[Case]) -> bool {
+    let case_returns = |case: &Case| {
+        case.body.iter().take_while(|statement| !matches!(statement.kind, StmtKind::Break)).any(returns)
+    };
+    cases.iter().any(|case| case.value.is_none())
+        && cases.last().is_some_and(case_returns)
+        && cases.iter().all(|case| case.body.is_empty() || case_returns(case))
+}
 
 struct Scope {
     variables: HashMap<String, Type>,
+    constants: HashSet<String>, // variables declared `const`
 }
 
 impl Scope {
     fn new() -> Scope {
-        Scope { variables: HashMap::new() }
+        Scope { variables: HashMap::new(), constants: HashSet::new() }
     }
 
     fn add_variable(&mut self, name: String, typ: Type) {
@@ -20,10 +64,6 @@ impl Scope {
     fn get_variable(&self, name: &str) -> Option<&Type> {
         self.variables.get(name)
     }
-
-    fn has_variable(&self, name: &str) -> bool {
-        self.variables.contains_key(name)
-    }
 }
 
 struct VariablesTable {
@@ -33,8 +73,8 @@ struct VariablesTable {
 impl VariablesTable {
     fn new() -> VariablesTable {
         VariablesTable {
-            scopes: vec![Scope::new(), Scope::new()],
-            // second scope currently stands for "main" function
+            scopes: vec![Scope::new()],
+            // first scope holds the global variables
         }
     }
 
@@ -59,106 +99,696 @@ impl VariablesTable {
         self.scopes.last_mut().unwrap().add_variable(name, typ);
     }
 
-    fn add_global_variable(&mut self, name: String, typ: types::Type) {
-        self.scopes.first_mut().unwrap().add_variable(name, typ);
+    fn add_constant(&mut self, name: String) {
+        self.scopes.last_mut().unwrap().constants.insert(name);
+    }
+
+    fn is_constant(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| scope.get_variable(name).is_some())
+            .is_some_and(|scope| scope.constants.contains(name))
+    }
+
+    fn is_global_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+}
+
+struct FunctionSignature {
+    return_type: Type,
+    params: Vec<Type>,
+}
+
+struct FunctionsTable {
+    functions: HashMap<String, FunctionSignature>,
+}
+
+impl FunctionsTable {
+    fn new() -> FunctionsTable {
+        FunctionsTable { functions: HashMap::new() }
+    }
+
+    fn add_function(&mut self, name: String, signature: FunctionSignature) {
+        self.functions.insert(name, signature);
+    }
+
+    fn get_function(&self, name: &str) -> Option<&FunctionSignature> {
+        self.functions.get(name)
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
     }
 }
 
 pub struct Semantics<'a> {
     variables_table: VariablesTable,
-    symbols: &'a [SymbolNode],
+    functions_table: FunctionsTable,
+    structs: StructTable,
+    types: TypeTable, // names of types, apart from the variables
+    enums: HashSet<String>,
+    enum_values: HashMap<String, i32>, // value of every enum variant, for folding constants
+    return_type: Option<Type>, // return type of the function being checked
+    switch_depth: usize,       // switches around the statement being checked, a `break` needs one
+    program: &'a Program,
 }
 impl<'a> Semantics<'a> {
-    pub fn new(symbols: &'a [SymbolNode]) -> Semantics<'a> {
+    pub fn new(program: &'a Program) -> Semantics<'a> {
         Semantics {
             variables_table: VariablesTable::new(),
-            symbols,
+            functions_table: FunctionsTable::new(),
+            structs: StructTable::new(),
+            types: TypeTable::new(),
+            enums: HashSet::new(),
+            enum_values: HashMap::new(),
+            return_type: None,
+            switch_depth: 0,
+            program,
         }
     }
 
-    pub fn validate_semantics(&mut self) {
-        for symbol in self.symbols {
-            match &symbol.value {
-                ASTNode::Declaration { typ, name } => {
-                    self.declaration(typ, name);
-                }
-                ASTNode::Assign { name, value } => {
-                    self.assignment(name, value);
+    /// Stops at the first error, which is reported at the span of the node it is about
+    pub fn validate_semantics(&mut self) -> Result<(), SemanticError> {
+        for item in &self.program.items {
+            self.item(item)?;
+        }
+        info!("semantics passed!");
+        Ok(())
+    }
+
+    fn item(&mut self, item: &Item) -> Result<(), SemanticError> {
+        match &item.kind {
+            ItemKind::Public(item) => self.item(item)?,
+            ItemKind::Global(declaration) => self.variable_declaration(declaration)?,
+            ItemKind::Function(function) => self.function(function)?,
+            ItemKind::Struct { name, fields } => self.struct_declaration(name, fields, item.span)?,
+            ItemKind::Enum { name, variants } => self.enum_declaration(name, variants, item.span)?,
+            ItemKind::TypeAlias { name, typ } => self.type_alias(name, typ, item.span)?,
+            ItemKind::Statement(Stmt { kind: StmtKind::Assign { target, .. } | StmtKind::CompoundAssign { target, .. }, .. }) => {
+                return Err(SemanticError::new(
+                    item.span,
+                    format!("semantics failed! assignment to {} outside of a function", target),
+                ));
+            }
+            _ => {
+                return Err(SemanticError::new(
+                    item.span,
+                    format!("semantics failed! {} is not a valid semantic", item),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn function(&mut self, function: &Function) -> Result<(), SemanticError> {
+        let Function { return_type, name, params, body, span } = function;
+        if self.functions_table.has_function(name) || self.variables_table.find_variable(name).is_some() {
+            return Err(SemanticError::new(*span, format!("semantics failed! {} is already declared", name)));
+        }
+        self.check_type(return_type, *span)?;
+        // strings and structs live in the caller's frame and cannot be handed back
+        if matches!(return_type, Type::String | Type::Struct(_)) {
+            return Err(SemanticError::new(
+                *span,
+                format!("semantics failed! function {} cannot return a value of type {}", name, return_type),
+            ));
+        }
+        if *return_type != Type::Void && !body.iter().any(returns) {
+            return Err(SemanticError::new(
+                *span,
+                format!("semantics failed! function {} must return a value of type {}", name, return_type),
+            ));
+        }
+
+        let param_types = params.iter().map(|param| param.typ.clone()).collect();
+        // registered before the body is checked so that the function can call itself
+        self.functions_table.add_function(
+            name.to_string(),
+            FunctionSignature { return_type: return_type.clone(), params: param_types },
+        );
+
+        // parameters live in their own scope, below the scope of the body
+        self.variables_table.add_scope();
+        for param in params {
+            if let Type::Array(..) | Type::Struct(_) = param.typ {
+                return Err(SemanticError::new(
+                    param.span,
+                    format!("semantics failed! {} of type {} cannot be passed to {} by value", param.name, param.typ, name),
+                ));
+            }
+            self.declaration(&param.typ, &param.name, param.span)?;
+        }
+
+        self.variables_table.add_scope();
+        self.return_type = Some(return_type.clone());
+        for statement in body {
+            self.statement(statement)?;
+        }
+        self.return_type = None;
+        self.variables_table.remove_scope();
+        self.variables_table.remove_scope();
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<(), SemanticError> {
+        match &statement.kind {
+            StmtKind::Declaration(declaration) => self.variable_declaration(declaration),
+            StmtKind::Assign { target, value } => self.assignment(target, value),
+            StmtKind::CompoundAssign { target, operation, value } => self.compound_assignment(target, *operation, value),
+            StmtKind::Return(value) => self.return_statement(value.as_ref(), statement.span),
+            StmtKind::Switch { value, cases } => self.switch_statement(value, cases),
+            StmtKind::Break if self.switch_depth == 0 => {
+                Err(SemanticError::new(statement.span, "semantics failed! break outside of a switch"))
+            }
+            StmtKind::Break => Ok(()),
+            StmtKind::Expr(expr) => self.expr_type(expr).map(|_| ()),
+            StmtKind::Error => {
+                Err(SemanticError::new(
+                    statement.span,
+                    format!("semantics failed! {} is not a valid statement", statement),
+                ))
+            }
+        }
+    }
+
+    /// Cases are distinct constants of the switched type, with at most one default.
+    /// Only an empty case falls through into the next one, any other ends with a break or a return
+    fn switch_statement(&mut self, value: &Expr, cases: &[Case]) -> Result<(), SemanticError> {
+        let typ = self.expr_type(value)?;
+        if !matches!(typ, Type::Integer | Type::Char | Type::Enum(_)) {
+            return Err(SemanticError::new(
+                value.span,
+                format!("semantics failed! cannot switch over {} of type {}", value, typ),
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        let mut has_default = false;
+        // the cases share one scope, like the block they are written in
+        self.variables_table.add_scope();
+        self.switch_depth += 1;
+        for (i, Case { value: label, body, span }) in cases.iter().enumerate() {
+            match label {
+                Some(label) => {
+                    let Some(constant) = label.evaluate_constant_in(&self.enum_values) else {
+                        return Err(SemanticError::new(
+                            label.span,
+                            format!("semantics failed! case {} is not a constant", label),
+                        ));
+                    };
+                    let label_type = self.expr_type(label)?;
+                    if Type::common(&typ, &label_type) != Some(typ.clone()) {
+                        return Err(SemanticError::new(
+                            label.span,
+                            format!("semantics failed! case {} of type {} in a switch over {}", label, label_type, typ),
+                        ));
+                    }
+                    if !seen.insert(constant) {
+                        return Err(SemanticError::new(
+                            label.span,
+                            format!("semantics failed! duplicate case {}", label),
+                        ));
+                    }
                 }
-                ASTNode::DeclarationAssignment { typ, name, value } => {
-                    self.declaration_assignment(typ, name, value);
+                None if has_default => {
+                    return Err(SemanticError::new(
+                        *span,
+                        format!("semantics failed! switch over {} has more than one default", value),
+                    ));
                 }
-                _ => {
-                    error!(
-                        "semantics failed! {:?} is not a valid semantic",
-                        symbol.value
-                    );
+                None => has_default = true,
+            }
+
+            for statement in body {
+                self.statement(statement)?;
+            }
+            let is_last = i + 1 == cases.len();
+            let ends = body.last().is_none_or(|statement| matches!(statement.kind, StmtKind::Break | StmtKind::Return(_)));
+            if !is_last && !ends {
+                let name = label.as_ref().map_or("default".to_string(), |label| label.to_string());
+                return Err(SemanticError::new(
+                    *span,
+                    format!("semantics failed! case {} falls through, end it with a break", name),
+                ));
+            }
+        }
+        self.switch_depth -= 1;
+        self.variables_table.remove_scope();
+        Ok(())
+    }
+
+    fn struct_declaration(&mut self, name: &str, fields: &[Field], span: Span) -> Result<(), SemanticError> {
+        if self.structs.contains_key(name) {
+            return Err(SemanticError::new(span, format!("semantics failed! struct {} is already declared", name)));
+        }
+        if fields.is_empty() {
+            return Err(SemanticError::new(span, format!("semantics failed! struct {} has no fields", name)));
+        }
+
+        let mut field_types: Vec<(String, Type)> = Vec::new();
+        for Field { typ, name: field_name, span } in fields {
+            if field_types.iter().any(|(existing, _)| existing == field_name) {
+                return Err(SemanticError::new(
+                    *span,
+                    format!("semantics failed! field {} of struct {} is declared twice", field_name, name),
+                ));
+            }
+            // the struct is only registered afterwards, so it cannot contain itself, only point to itself
+            if *typ != Type::Pointer(Box::new(Type::Struct(name.to_string()))) {
+                self.check_type(typ, *span)?;
+            }
+            if *typ == Type::Void {
+                return Err(SemanticError::new(
+                    *span,
+                    format!("semantics failed! field {} of struct {} cannot be of type void", field_name, name),
+                ));
+            }
+            field_types.push((field_name.to_string(), typ.clone()));
+        }
+
+        let layout = StructLayout::new(&field_types, &self.structs);
+        self.structs.insert(name.to_string(), layout);
+        Ok(())
+    }
+
+    /// Variants are global constants of the enum's type, numbered from 0 unless a value is given
+    fn enum_declaration(&mut self, name: &str, variants: &[Variant], span: Span) -> Result<(), SemanticError> {
+        if !self.enums.insert(name.to_string()) {
+            return Err(SemanticError::new(span, format!("semantics failed! enum {} is already declared", name)));
+        }
+        if variants.is_empty() {
+            return Err(SemanticError::new(span, format!("semantics failed! enum {} has no variants", name)));
+        }
+
+        let mut next = 0;
+        for Variant { name: variant_name, value, span } in variants {
+            let value = match value {
+                Some(value) => {
+                    let Some(constant) = value.evaluate_constant_in(&self.enum_values) else {
+                        return Err(SemanticError::new(
+                            value.span,
+                            format!("semantics failed! value of {} is not a constant", variant_name),
+                        ));
+                    };
+                    let message = format!("semantics failed! value of {} must be an Integer", variant_name);
+                    self.validate_expr_type(value, &Type::Integer, &message)?;
+                    constant
                 }
+                None => next,
+            };
+            self.declaration(&Type::Enum(name.to_string()), variant_name, *span)?;
+            self.variables_table.add_constant(variant_name.clone());
+            self.enum_values.insert(variant_name.clone(), value);
+            next = value.wrapping_add(1);
+        }
+        Ok(())
+    }
+
+    /// `typedef` gives another name to a type whose structs and enums are declared already
+    fn type_alias(&mut self, name: &str, typ: &Type, span: Span) -> Result<(), SemanticError> {
+        self.check_type(typ, span)?;
+        if !self.types.add_alias(name, typ.clone()) {
+            return Err(SemanticError::new(span, format!("semantics failed! type {} is already declared", name)));
+        }
+        Ok(())
+    }
+
+    /// Every struct used in a type must be declared before
+    fn check_type(&self, typ: &Type, span: Span) -> Result<(), SemanticError> {
+        match typ {
+            Type::Array(element, _) | Type::Pointer(element) => self.check_type(element, span),
+            Type::Struct(name) if !self.structs.contains_key(name) => {
+                Err(SemanticError::new(span, format!("semantics failed! struct {} is not declared", name)))
+            }
+            Type::Enum(name) if !self.enums.contains(name) => {
+                Err(SemanticError::new(span, format!("semantics failed! enum {} is not declared", name)))
             }
+            _ => Ok(()),
         }
-        info!("semantics passed!");
     }
 
-    fn declaration(&mut self, typ: &Type, name_ast: &Box<ASTNode>) {
-        if let ASTNode::Identifier(name) = name_ast.as_ref() {
-            if self.variables_table.find_variable(name).is_some() {
-                error!("semantics failed! variable {} is already declared", name);
-                std::process::exit(1);
+    fn declaration(&mut self, typ: &Type, name: &str, span: Span) -> Result<(), SemanticError> {
+        self.check_type(typ, span)?;
+        if self.variables_table.find_variable(name).is_some() || self.functions_table.has_function(name) {
+            return Err(SemanticError::new(span, format!("semantics failed! variable {} is already declared", name)));
+        }
+        if *typ == Type::Void {
+            return Err(SemanticError::new(span, format!("semantics failed! variable {} cannot be of type void", name)));
+        }
+        self.variables_table.add_variable(name.to_string(), typ.clone());
+        Ok(())
+    }
+
+    /// A declaration with or without a value. Every constant gets its value where it is declared,
+    /// nothing writes to it afterwards
+    fn variable_declaration(&mut self, declaration: &Declaration) -> Result<(), SemanticError> {
+        let Declaration { typ, name, value, constant, span } = declaration;
+        match value {
+            Some(value) => self.declaration_assignment(typ, name, value, *span)?,
+            None if *constant => {
+                return Err(SemanticError::new(
+                    *span,
+                    format!("semantics failed! constant {} needs an initializer", name),
+                ));
             }
-            self.variables_table.add_variable(name.clone(), typ.clone());
-        } else {
-            error!("invalid declaration {:?}", name_ast);
-            std::process::exit(1);
+            None => self.declaration(typ, name, *span)?,
+        }
+        // marked only afterwards, the initializer is the one write it gets
+        if *constant {
+            self.variables_table.add_constant(name.clone());
         }
+        Ok(())
     }
 
-    fn assignment(&mut self, name_ast: &Box<ASTNode>, value_ast: &Box<ASTNode>) {
-        if let ASTNode::Identifier(name) = name_ast.as_ref() {
-            if self.variables_table.find_variable(name).is_none() {
-                error!("variable {} is not declared", name);
-                std::process::exit(1);
+    /// The constant that a write to `target` would change, a pointer may point to anything but a constant
+    fn written_constant<'n>(&self, target: &'n Expr) -> Option<&'n Expr> {
+        match &target.kind {
+            ExprKind::Index { array: inner, .. } | ExprKind::Member { object: inner, .. } => self.written_constant(inner),
+            ExprKind::Identifier(name) if self.variables_table.is_constant(name) => Some(target),
+            _ => None,
+        }
+    }
+
+    /// Type of a variable, element, field or dereferenced pointer that is written to
+    fn target_type(&self, target: &Expr) -> Result<Type, SemanticError> {
+        if let Some(constant) = self.written_constant(target) {
+            return Err(SemanticError::new(
+                target.span,
+                format!("semantics failed! constant {} cannot be written", constant),
+            ));
+        }
+        match &target.kind {
+            ExprKind::Identifier(_) | ExprKind::Index { .. } | ExprKind::Member { .. } => self.expr_type(target),
+            ExprKind::Unary { operation: UnaryOp::Deref, .. } => self.expr_type(target),
+            _ => Err(SemanticError::new(target.span, format!("invalid assignment {}", target))),
+        }
+    }
+
+    fn assignment(&mut self, target: &Expr, value: &Expr) -> Result<(), SemanticError> {
+        let typ = self.target_type(target)?;
+        if let Type::Array(..) = typ {
+            return Err(SemanticError::new(
+                target.span,
+                format!("semantics failed! array {} cannot be assigned as a whole", target),
+            ));
+        }
+        self.validate_expr_type(value, &typ, "type mismatch")?;
+        // string variables are fixed-size buffers that also hold the terminating zero
+        if let ExprKind::String(literal) = &value.kind
+            && literal.len() >= typ.to_size_asm(&self.structs) as usize
+        {
+            return Err(SemanticError::new(value.span, format!("string literal assigned to {} is too long", target)));
+        }
+        Ok(())
+    }
+
+    /// `x op= value` is `x = x op value` with `x` evaluated once, so it follows the rules of `op`
+    fn compound_assignment(&self, target: &Expr, operation: AssignOp, value: &Expr) -> Result<(), SemanticError> {
+        let typ = self.target_type(target)?;
+        let span = target.span.to(value.span);
+        let value_type = widened(self.expr_type(value)?);
+
+        // a pointer only moves by whole elements, `p -= q` would not leave a pointer behind
+        let pointer_result = match operation.binary() {
+            Some(binary) => self.pointer_arithmetic_type(binary, &typ, &value_type, span)?,
+            None => None,
+        };
+        if let Some(result) = pointer_result {
+            if result != typ {
+                return Err(SemanticError::new(
+                    span,
+                    format!("semantics failed! {} {:?} {} does not result in a value of type {}", target, operation, value, typ),
+                ));
             }
-            if let ASTNode::Expr(expr) = value_ast.as_ref() {
-                if !Self::validate_expr_type(
-                    expr,
-                    self.variables_table.find_variable(name).unwrap(),
-                ) {
-                    error!("type mismatch");
-                    std::process::exit(1);
+            return Ok(());
+        }
+        if typ != Type::Integer && typ != Type::Char {
+            return Err(SemanticError::new(
+                target.span,
+                format!("operator {:?} cannot be applied to {}", operation, typ),
+            ));
+        }
+        if value_type != typ {
+            return Err(SemanticError::new(
+                value.span,
+                format!("mismatched types in {:?}\n\tTarget: {}\n\tValue: {}", operation, typ, value_type),
+            ));
+        }
+        Ok(())
+    }
+
+    fn declaration_assignment(&mut self, typ: &Type, name: &str, value: &Expr, span: Span) -> Result<(), SemanticError> {
+        // globals are placed in the data section, so their value must be known at compile time
+        let is_constant = match typ {
+            Type::String => matches!(value.kind, ExprKind::String(_)),
+            _ => value.evaluate_constant_in(&self.enum_values).is_some(),
+        };
+        if self.variables_table.is_global_scope() && !is_constant {
+            return Err(SemanticError::new(
+                value.span,
+                format!("semantics failed! initializer of global {} is not a constant", name),
+            ));
+        }
+        self.declaration(typ, name, span)?;
+        self.assignment(&Expr::new(ExprKind::Identifier(name.to_string()), span), value)
+    }
+
+    fn return_statement(&self, value: Option<&Expr>, span: Span) -> Result<(), SemanticError> {
+        let expected = self.return_type.clone().expect("return outside of a function");
+        match value {
+            Some(value) => {
+                if expected == Type::Void {
+                    return Err(SemanticError::new(value.span, "semantics failed! void function cannot return a value"));
+                }
+                self.validate_expr_type(value, &expected, "return type mismatch")?;
+            }
+            None => {
+                if expected != Type::Void {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("semantics failed! missing return value of type {}", expected),
+                    ));
                 }
             }
-        } else {
-            error!("invalid assignment {:?}", name_ast);
-            std::process::exit(1);
         }
+        Ok(())
     }
 
-    fn declaration_assignment(&mut self, typ: &Type, name_ast: &Box<ASTNode>, value_ast: &Box<ASTNode>) {
-        self.declaration(typ, name_ast);
-        self.assignment(name_ast, value_ast);
+    fn call_type(&self, name: &str, args: &[Expr], span: Span) -> Result<Type, SemanticError> {
+        let Some(function) = self.functions_table.get_function(name) else {
+            return Err(SemanticError::new(span, format!("function {} is not declared", name)));
+        };
+        if function.params.len() != args.len() {
+            return Err(SemanticError::new(
+                span,
+                format!("function {} takes {} arguments but {} were given", name, function.params.len(), args.len()),
+            ));
+        }
+        for (i, (arg, param)) in args.iter().zip(&function.params).enumerate() {
+            self.validate_expr_type(arg, param, &format!("argument {} of {} has the wrong type", i + 1, name))?;
+        }
+        Ok(function.return_type.clone())
+    }
+
+    /// An error with the message, followed by both types, unless the expression is of the expected type
+    fn validate_expr_type(&self, head: &Expr, expected_type: &Type, message: &str) -> Result<(), SemanticError> {
+        let actual = self.expr_type(head)?;
+        // an enum value may stand for an int, but an enum variable only takes values of its enum
+        let widens = *expected_type == Type::Integer && matches!(actual, Type::Enum(_));
+        if &actual != expected_type && !widens {
+            let message = format!("{}\n\tExpected: {}\n\tGot: {}", message, expected_type, actual);
+            return Err(SemanticError::new(head.span, message));
+        }
+        Ok(())
     }
 
-    fn validate_expr_type(head: &ASTNode, expected_type: &Type) -> bool {
-        match head {
-            ASTNode::BinaryOperation { left, right, operation: _ } => {
-                Self::validate_expr_type(left, expected_type)
-                    && Self::validate_expr_type(right, expected_type)
+    fn expr_type(&self, head: &Expr) -> Result<Type, SemanticError> {
+        let span = head.span;
+        let typ = match &head.kind {
+            ExprKind::Identifier(name) => match self.variables_table.find_variable(name) {
+                Some(typ) => typ.clone(),
+                None => {
+                    return Err(SemanticError::new(span, format!("variable {} is not declared", name)));
+                }
+            },
+            ExprKind::Call { name, args } => self.call_type(name, args, span)?,
+            ExprKind::Index { array, index } => {
+                let Type::Array(element, _) = self.expr_type(array)? else {
+                    return Err(SemanticError::new(array.span, format!("semantics failed! {} is not an array", array)));
+                };
+                if widened(self.expr_type(index)?) != Type::Integer {
+                    return Err(SemanticError::new(
+                        index.span,
+                        format!("semantics failed! index of {} must be an Integer", array),
+                    ));
+                }
+                *element
             }
-            _ => {
-                if let Some(actual) = head.inferred_type() {
-                    if &actual == expected_type {
-                        true
-                    } else {
-                        error!(
-                            "mismatched types\n\tExpected: {:?}\n\tGot: {:?}",
-                            expected_type, actual
-                        );
-                        false
+            ExprKind::Member { object, field } => {
+                let Type::Struct(name) = self.expr_type(object)? else {
+                    return Err(SemanticError::new(
+                        object.span,
+                        format!("semantics failed! {} is not a struct", object),
+                    ));
+                };
+                match self.structs[&name].get_field(field) {
+                    Some(field) => field.typ.clone(),
+                    None => {
+                        return Err(SemanticError::new(
+                            span,
+                            format!("semantics failed! struct {} has no field {}", name, field),
+                        ));
+                    }
+                }
+            }
+            ExprKind::Unary { operation, operand } => self.unary_type(*operation, operand, span)?,
+            ExprKind::Cast { typ, operand } => {
+                self.check_type(typ, span)?;
+                let from = self.expr_type(operand)?;
+                let allowed = from == *typ
+                    || CASTS.contains(&(from.clone(), typ.clone()))
+                    || matches!(
+                        (&from, typ),
+                        (Type::Pointer(_), Type::Pointer(_)) | (Type::Enum(_), Type::Integer) | (Type::Integer, Type::Enum(_))
+                    );
+                if !allowed {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("semantics failed! {} of type {} cannot be cast to {}", operand, from, typ),
+                    ));
+                }
+                typ.clone()
+            }
+            ExprKind::Conditional { condition, if_true, if_false } => {
+                if self.expr_type(condition)? != Type::Bool {
+                    return Err(SemanticError::new(
+                        condition.span,
+                        format!("semantics failed! condition {} must be a Bool", condition),
+                    ));
+                }
+                let (true_type, false_type) = (self.expr_type(if_true)?, self.expr_type(if_false)?);
+                match Type::common(&true_type, &false_type) {
+                    Some(typ) => typ,
+                    None => {
+                        return Err(SemanticError::new(
+                            span,
+                            format!("mismatched types in the arms of {}\n\tTrue: {}\n\tFalse: {}", head, true_type, false_type),
+                        ));
                     }
-                } else {
-                    false
                 }
             }
+            ExprKind::Update { operation, operand, .. } => {
+                let typ = self.target_type(operand)?;
+                let movable = match &typ {
+                    Type::Integer | Type::Char => true,
+                    Type::Pointer(target) => target.to_size_asm(&self.structs) != 0,
+                    _ => false,
+                };
+                if !movable {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("operator {:?} cannot be applied to {} of type {}", operation, operand, typ),
+                    ));
+                }
+                typ
+            }
+            ExprKind::Binary { left, operation, right } => {
+                let left = widened(self.expr_type(left)?);
+                let right = widened(self.expr_type(right)?);
+                if let Some(typ) = self.pointer_arithmetic_type(*operation, &left, &right, span)? {
+                    return Ok(typ);
+                }
+                if left != right {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("mismatched types in {:?}\n\tLeft: {}\n\tRight: {}", operation, left, right),
+                    ));
+                }
+                if left != Type::Integer && left != Type::Char {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("operator {:?} cannot be applied to {}", operation, left),
+                    ));
+                }
+                left
+            }
+            _ => match head.inferred_type() {
+                Some(typ) => typ,
+                None => {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("semantics failed! {} is not a valid expression", head),
+                    ));
+                }
+            },
+        };
+        Ok(typ)
+    }
+
+    /// `&x` points to an lvalue, `*p` reads through a pointer
+    fn unary_type(&self, operation: UnaryOp, operand: &Expr, span: Span) -> Result<Type, SemanticError> {
+        match operation {
+            UnaryOp::AddressOf => {
+                let is_lvalue = matches!(
+                    operand.kind,
+                    ExprKind::Identifier(_) | ExprKind::Index { .. } | ExprKind::Member { .. } | ExprKind::Unary { operation: UnaryOp::Deref, .. }
+                );
+                if !is_lvalue {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("semantics failed! cannot take the address of {}", operand),
+                    ));
+                }
+                // the pointer would allow writing to the constant
+                if let Some(constant) = self.written_constant(operand) {
+                    return Err(SemanticError::new(
+                        span,
+                        format!("semantics failed! cannot take the address of constant {}", constant),
+                    ));
+                }
+                Ok(Type::Pointer(Box::new(self.expr_type(operand)?)))
+            }
+            UnaryOp::Deref => match self.expr_type(operand)? {
+                Type::Pointer(target) if *target != Type::Void => Ok(*target),
+                typ => {
+                    let message = format!("semantics failed! {} of type {} cannot be dereferenced", operand, typ);
+                    Err(SemanticError::new(span, message))
+                }
+            },
+        }
+    }
+
+    /// `p + n` and `p - n` move a pointer by whole elements, `p - q` counts the elements between two pointers
+    fn pointer_arithmetic_type(
+        &self,
+        operator: BinOp,
+        left: &Type,
+        right: &Type,
+        span: Span,
+    ) -> Result<Option<Type>, SemanticError> {
+        let typ = match (operator, left, right) {
+            (BinOp::Add | BinOp::Sub, Type::Pointer(_), Type::Integer) => left.clone(),
+            (BinOp::Add, Type::Integer, Type::Pointer(_)) => right.clone(),
+            (BinOp::Sub, Type::Pointer(_), Type::Pointer(_)) if left == right => Type::Integer,
+            (_, Type::Pointer(_), _) | (_, _, Type::Pointer(_)) => {
+                return Err(SemanticError::new(
+                    span,
+                    format!("operator {:?} cannot be applied to pointers\n\tLeft: {}\n\tRight: {}", operator, left, right),
+                ));
+            }
+            _ => return Ok(None),
+        };
+        let pointer = if matches!(left, Type::Pointer(_)) { left } else { right };
+        if let Type::Pointer(target) = pointer
+            && target.to_size_asm(&self.structs) == 0
+        {
+            return Err(SemanticError::new(
+                span,
+                format!("semantics failed! arithmetic on {} needs a sized target type", pointer),
+            ));
         }
+        Ok(Some(typ))
     }
 }
@@ -20,7 +20,7 @@ pub const BRIGHT_CYAN: &str = "\x1b[96m";
 #[macro_export]
 macro_rules! debug {
     ($($arg:tt)*) => {{
-        use crate::utils::{CYAN, YELLOW, RESET};
+        use $crate::utils::{CYAN, YELLOW, RESET};
         print!("{}DEBUG:{} ", CYAN, RESET);
         println!("{}{}{}", YELLOW, format!($($arg)*), RESET);
     }};
@@ -29,7 +29,7 @@ macro_rules! debug {
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {{
-        use crate::utils::{GREEN, WHITE, RESET};
+        use $crate::utils::{GREEN, WHITE, RESET};
         print!("{}INFO:{} ", GREEN, RESET);
         println!("{}{}{}", WHITE, format!($($arg)*), RESET);
     }};
@@ -38,7 +38,7 @@ macro_rules! info {
 #[macro_export]
 macro_rules! error {
     ($($arg:tt)*) => {{
-        use crate::utils::{RED, WHITE, RESET};
+        use $crate::utils::{RED, WHITE, RESET};
         print!("{}ERROR:{} ", RED, RESET);
         println!("{}{}{}", WHITE, format!($($arg)*), RESET);
     }};
@@ -0,0 +1,96 @@
+use std::fs;
+use compiler::code_gen::generator::Generator;
+use compiler::parser::parser::Parser;
+use compiler::semantics::semantics::Semantics;
+
+/// The code section generated for the source, after it passed the semantic checks
+fn generate(name: &str, source: &str) -> String {
+    let file_name = format!("{}.txt", name);
+    fs::write(&file_name, source).unwrap();
+    let program = Parser::new(&file_name).parse().unwrap_or_else(|errors| panic!("{:?} in\n{}", errors, source));
+    Semantics::new(&program).validate_semantics().unwrap_or_else(|error| panic!("{} in\n{}", error, source));
+    Generator::new(&program, name).generate();
+    fs::read_to_string(format!("{}.code", name)).unwrap()
+}
+
+fn lines(code: &str) -> Vec<&str> {
+    code.lines().map(str::trim).collect()
+}
+
+#[test]
+fn test_dense_switch_jumps_through_a_table() {
+    let source = "int f(int x) {
+    switch (x) {
+        case 1: return 10;
+        case 2: return 20;
+        case 4: return 40;
+        case 5: return 50;
+        default: return 0;
+    }
+    return 0;
+}";
+    let code = generate("test_codegen_dense", source);
+    let code = lines(&code);
+
+    assert!(code.contains(&"sub eax, 1"), "{:#?}", code);
+    assert!(code.contains(&"cmp eax, 4"), "{:#?}", code);
+    assert!(code.contains(&"jmp qword ptr [rcx + rax*8]"), "{:#?}", code);
+    assert!(!code.iter().any(|line| line.starts_with("je ")), "no case is compared: {:#?}", code);
+    // one entry per value from 1 to 5, the missing 3 goes to the default
+    let table = code.iter().position(|line| line.starts_with(".Ljump_table")).expect("the table is written with the code");
+    assert_eq!(code[table - 2..table], [".section .rodata", ".balign 8"], "the table is read-only");
+    let entries: Vec<&str> = code[table + 1..].iter().copied().take_while(|line| line.starts_with(".quad")).collect();
+    assert_eq!(code[table + 1 + entries.len()], ".text");
+    let default = code.iter().find_map(|line| line.strip_prefix("ja ")).expect("values out of the table go to the default");
+    assert_eq!(entries.len(), 5, "{:#?}", code);
+    assert_eq!(entries[2], format!(".quad {}", default));
+    assert!(entries.iter().enumerate().all(|(i, entry)| i == 2 || !entry.ends_with(default)), "{:#?}", code);
+}
+
+#[test]
+fn test_sparse_switch_compares_every_case() {
+    let source = "int f(int x) {
+    switch (x) {
+        case 1: return 10;
+        case 100: return 20;
+        case 1000: return 30;
+        case 10000: return 40;
+    }
+    return 0;
+}";
+    let code = generate("test_codegen_sparse", source);
+    let code = lines(&code);
+
+    let compares: Vec<&&str> = code.iter().filter(|line| line.starts_with("cmp eax, ")).collect();
+    assert_eq!(compares, [&"cmp eax, 1", &"cmp eax, 100", &"cmp eax, 1000", &"cmp eax, 10000"]);
+    assert_eq!(code.iter().filter(|line| line.starts_with("je .Lcase")).count(), 4);
+    assert!(!code.iter().any(|line| line.contains("rax*8")), "{:#?}", code);
+    // without a default, a value matching no case leaves the switch
+    let compare_end = code.iter().position(|line| *line == "cmp eax, 10000").unwrap() + 2;
+    assert!(code[compare_end].starts_with("jmp .Lswitch_end"), "{:#?}", code);
+}
+
+#[test]
+fn test_conditional_evaluates_only_the_chosen_arm() {
+    let source = "int g(int x) {
+    return x;
+}
+int f(int a, int b) {
+    return (bool) a ? g(a) : g(b);
+}";
+    let code = generate("test_codegen_conditional", source);
+    let code = lines(&code);
+
+    let branch = code.iter().position(|line| line.starts_with("je .Lelse")).expect("the condition branches");
+    let else_label = format!("{}:", &code[branch][3..]);
+    let jump = code.iter().position(|line| line.starts_with("jmp .Lend")).unwrap();
+    let end_label = format!("{}:", &code[jump][4..]);
+    let else_at = code.iter().position(|line| *line == else_label).unwrap();
+    let end_at = code.iter().position(|line| *line == end_label).unwrap();
+
+    // each arm calls once, the true one before the jump over the false one
+    let calls = |range: std::ops::Range<usize>| code[range].iter().filter(|line| **line == "call g").count();
+    assert!(branch < jump && jump < else_at && else_at < end_at, "{:#?}", code);
+    assert_eq!(calls(branch..jump), 1, "{:#?}", code);
+    assert_eq!(calls(else_at..end_at), 1, "{:#?}", code);
+}
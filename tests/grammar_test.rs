@@ -0,0 +1,63 @@
+use compiler::lexer::lexer::SymbolKind;
+use compiler::parser::ast::ASTNode;
+use compiler::parser::rules::{Pattern, Rule};
+use compiler::parser::table::{ConflictKind, ParseTable, TABLE};
+use compiler::parser::types::{OperatorType, Punctuation};
+
+fn rule(result: SymbolKind, pattern: Vec<Pattern>) -> Rule {
+    Rule { result, pattern, action: |_, _| Ok(ASTNode::Empty()), precedence: None }
+}
+
+#[test]
+fn test_grammar_has_no_conflicts() {
+    let conflicts: Vec<String> = TABLE.conflicts().iter().map(|conflict| conflict.to_string()).collect();
+    assert!(conflicts.is_empty(), "grammar conflicts:\n{}", conflicts.join("\n"));
+}
+
+#[test]
+fn test_conflicts_are_reported_with_examples() {
+    use Pattern::Kind;
+    // `1 + 1 + 1` can be grouped either way without precedence, and `x` is both a call and an index
+    let rules = vec![
+        rule(SymbolKind::Program, vec![]),
+        rule(SymbolKind::Program, vec![Kind(SymbolKind::Program), Kind(SymbolKind::ExpressionStatement)]),
+        rule(
+            SymbolKind::ExpressionStatement,
+            vec![Kind(SymbolKind::Expr), Pattern::Punctuation(Punctuation::Semicolon)],
+        ),
+        rule(
+            SymbolKind::Expr,
+            vec![Kind(SymbolKind::Expr), Pattern::Operator(OperatorType::Add), Kind(SymbolKind::Expr)],
+        ),
+        rule(SymbolKind::Expr, vec![Kind(SymbolKind::Number)]),
+        rule(SymbolKind::Expr, vec![Kind(SymbolKind::Call)]),
+        rule(SymbolKind::Expr, vec![Kind(SymbolKind::Index)]),
+        rule(SymbolKind::Call, vec![Kind(SymbolKind::Identifier)]),
+        rule(SymbolKind::Index, vec![Kind(SymbolKind::Identifier)]),
+    ];
+    let table = ParseTable::new(&rules, SymbolKind::Program);
+
+    let shift_reduce: Vec<String> = table
+        .conflicts()
+        .iter()
+        .filter(|conflict| conflict.kind == ConflictKind::ShiftReduce)
+        .map(|conflict| conflict.to_string())
+        .collect();
+    assert_eq!(shift_reduce.len(), 1, "unexpected shift/reduce conflicts: {:?}", shift_reduce);
+    assert!(
+        shift_reduce[0].contains("Expr -> Expr '+' Expr") && shift_reduce[0].contains("e.g. `1 + 1` followed by `+`"),
+        "unexpected report: {}",
+        shift_reduce[0]
+    );
+
+    let reduce_reduce: Vec<_> = table
+        .conflicts()
+        .iter()
+        .filter(|conflict| conflict.kind == ConflictKind::ReduceReduce)
+        .collect();
+    assert!(!reduce_reduce.is_empty(), "the call/index ambiguity was not reported");
+    for conflict in reduce_reduce {
+        assert_eq!(conflict.rules, vec!["Call -> Identifier", "Index -> Identifier"]);
+        assert!(conflict.example.ends_with('x'), "unexpected example: {}", conflict);
+    }
+}
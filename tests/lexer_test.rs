@@ -38,3 +38,46 @@ fn test_basic_tokens() {
     let actual: Vec<_> = tokens.iter().map(|t| t.get_kind()).collect();
     assert_eq!(actual, expected, "Token kinds do not match!");
 }
+
+#[test]
+fn test_longest_operator_is_taken() {
+    let file_name = "lexer_test_operators.txt";
+    fs::write(file_name, "x<<=1;i+++--j;p=&y").unwrap();
+    let mut lexer = Lexer::new(file_name);
+
+    let mut operators = Vec::new();
+    while let Some(token) = lexer.get_next_token() {
+        if token.get_kind() == SymbolKind::Operator {
+            operators.push(token.get_value());
+        }
+    }
+    assert_eq!(operators, vec!["<<=", "++", "+", "--", "=", "&"]);
+}
+
+#[test]
+fn test_tokens_span_their_characters() {
+    let file_name = "lexer_test_spans.txt";
+    fs::write(file_name, "int x = \"ab\";\n  y += 10;").unwrap();
+    let mut lexer = Lexer::new(file_name);
+
+    let mut spans = Vec::new();
+    while let Some(token) = lexer.get_next_token() {
+        if token.get_kind() != SymbolKind::Whitespace {
+            let span = token.get_span();
+            spans.push((token.get_value(), span.line, span.column, span.end_line, span.end_column));
+        }
+    }
+    // a string spans its quotes, the lexeme is what is between them
+    let expected = vec![
+        ("int".to_string(), 1, 1, 1, 3),
+        ("x".to_string(), 1, 5, 1, 5),
+        ("=".to_string(), 1, 7, 1, 7),
+        ("ab".to_string(), 1, 9, 1, 12),
+        (";".to_string(), 1, 13, 1, 13),
+        ("y".to_string(), 2, 3, 2, 3),
+        ("+=".to_string(), 2, 5, 2, 6),
+        ("10".to_string(), 2, 8, 2, 9),
+        (";".to_string(), 2, 10, 2, 10),
+    ];
+    assert_eq!(spans, expected);
+}
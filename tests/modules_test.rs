@@ -0,0 +1,106 @@
+use std::fs;
+use compiler::modules::error::ModuleError;
+use compiler::modules::modules::ModuleLoader;
+use compiler::code_gen::generator::Generator;
+use compiler::semantics::semantics::Semantics;
+
+/// Writes the files of a program into its own directory, returns the path of the first one
+fn write_program(directory: &str, files: &[(&str, &str)]) -> String {
+    fs::create_dir_all(directory).unwrap();
+    for (name, source) in files {
+        fs::write(format!("{}/{}", directory, name), source).unwrap();
+    }
+    format!("{}/{}", directory, files[0].0)
+}
+
+#[test]
+fn test_imported_names_are_prefixed_by_their_module() {
+    let main = write_program(
+        "test_modules_names",
+        &[
+            ("main.src", "import \"math.src\";\nint helper;\nint main() {\n    Num n = square(2);\n    return n;\n}"),
+            ("math.src", "pub typedef int Num;\nint helper(int x) {\n    return x;\n}\npub Num square(int x) {\n    return helper(x) * x;\n}\npub int count;\nint total;"),
+        ],
+    );
+
+    let program = ModuleLoader::new(|_| {}).load(&main).unwrap();
+    let items: Vec<String> = program.items.iter().map(|item| item.to_string()).collect();
+
+    assert_eq!(items.len(), 7, "{:#?}", items);
+    assert_eq!(items[0], "Public(TypeAlias(math.Num, Integer))");
+    assert!(items[1].starts_with("Function(Integer, math.helper,"), "{}", items[1]);
+    assert!(items[2].contains("math.helper") && items[2].starts_with("Public(Function(Integer, math.square,"), "{}", items[2]);
+    assert_eq!(items[3], "Public(Declaration(Integer, math.count))");
+    assert_eq!(items[4], "Declaration(Integer, math.total)");
+    assert_eq!(items[5], "Declaration(Integer, helper)", "the names of the program itself are kept");
+    assert!(items[6].contains("Declaration(Integer, n, Call(math.square, [2]))"), "{}", items[6]);
+    let modules: Vec<Option<&str>> = program.items.iter().map(|item| item.module.as_deref()).collect();
+    assert_eq!(modules, [Some("math"), Some("math"), Some("math"), Some("math"), Some("math"), None, None]);
+
+    // only the pub items of a module and the program's own are visible to the linker
+    Semantics::new(&program).validate_semantics().unwrap();
+    Generator::new(&program, "test_modules_names/out").generate();
+    let globals = |output: &str| -> Vec<String> {
+        let lines = fs::read_to_string(format!("test_modules_names/out.{}", output)).unwrap();
+        lines.lines().filter(|line| line.starts_with(".globl")).map(String::from).collect()
+    };
+    assert_eq!(globals("code"), [".globl math.square", ".globl main"]);
+    assert_eq!(globals("data"), [".globl math.count", ".globl helper"]);
+}
+
+#[test]
+fn test_private_names_and_cycles_are_reported() {
+    let main = write_program(
+        "test_modules_cycle",
+        &[
+            ("main.src", "import \"a.src\";\nint main() {\n    return 0;\n}"),
+            ("a.src", "import \"b.src\";\npub int a;"),
+            ("b.src", "import \"a.src\";\nint b;"),
+        ],
+    );
+    let errors = ModuleLoader::new(|_| {}).load(&main).unwrap_err();
+    assert_eq!(errors, vec![ModuleError::Cycle { chain: vec!["a.src".into(), "b.src".into(), "a.src".into()] }]);
+    assert_eq!(errors[0].to_string(), "import cycle: a.src -> b.src -> a.src");
+
+    let main = write_program(
+        "test_modules_conflict",
+        &[
+            ("main.src", "import \"lib.src\";\nint value;\nimport \"missing.src\";"),
+            ("lib.src", "pub int value = 1;\nint hidden;"),
+        ],
+    );
+    let errors = ModuleLoader::new(|_| {}).load(&main).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ModuleError::LateImport { module: "main.src".into(), line: 3 },
+            ModuleError::Conflict { module: "main.src".into(), name: "value".into() },
+        ]
+    );
+
+    // a name that is not pub stays in its module, using it elsewhere is using an undeclared name
+    let main = write_program(
+        "test_modules_private",
+        &[
+            ("main.src", "import \"lib.src\";\nint main() {\n    return hidden(1);\n}"),
+            ("lib.src", "int hidden(int x) {\n    return x;\n}\npub int shown(int x) {\n    return hidden(x);\n}"),
+        ],
+    );
+    let program = ModuleLoader::new(|_| {}).load(&main).unwrap();
+    let error = Semantics::new(&program).validate_semantics().unwrap_err();
+    assert_eq!(error.message, "function hidden is not declared");
+    assert_eq!(error.span.line, 3);
+
+    // `a-b.src` and `a_b.src` would both name their symbols `a_b.*`
+    let main = write_program(
+        "test_modules_same_name",
+        &[
+            ("main.src", "import \"a-b.src\";\nimport \"a_b.src\";\nint main() {\n    return 0;\n}"),
+            ("a-b.src", "pub int x;"),
+            ("a_b.src", "pub int y;"),
+        ],
+    );
+    let errors = ModuleLoader::new(|_| {}).load(&main).unwrap_err();
+    assert_eq!(errors, vec![ModuleError::SameName { module: "a_b.src".into(), other: "a-b.src".into(), name: "a_b".into() }]);
+    assert_eq!(errors[0].to_string(), "a_b.src: module name a_b is already the name of a-b.src");
+}
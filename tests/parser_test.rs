@@ -1,53 +1,645 @@
 use std::fs;
-use compiler::lexer::lexer::SymbolKind;
-use compiler::parser::ast::{ASTNode, SymbolNode};
-use compiler::parser::types::{OperatorType, Type};
+use compiler::parser::error::ParseError;
+use compiler::parser::program::{
+    AssignOp, BinOp, Case, Declaration, Expr, ExprKind, Field, Function, Item, ItemKind, Param, Program, Stmt, StmtKind, UnaryOp, UpdateOp,
+};
+use compiler::parser::span::Span;
+use compiler::parser::types::Type;
+use compiler::parser::visit::{walk_expr, walk_expr_mut, Visitor, VisitorMut};
 use compiler::parser::parser::Parser;
+use compiler::parser::trace::TraceLevel;
 
 
 const FILE_NAME: &str = "test_parser.txt";
 
+fn number(n: i32) -> Expr {
+    Expr::from(ExprKind::Number(n))
+}
+
+fn identifier(name: &str) -> Expr {
+    Expr::from(ExprKind::Identifier(name.into()))
+}
+
+fn binary(left: Expr, operation: BinOp, right: Expr) -> Expr {
+    Expr::from(ExprKind::Binary { left: Box::new(left), operation, right: Box::new(right) })
+}
+
+fn unary(operation: UnaryOp, operand: Expr) -> Expr {
+    Expr::from(ExprKind::Unary { operation, operand: Box::new(operand) })
+}
+
+fn declaration(typ: Type, name: &str, value: Option<Expr>) -> Declaration {
+    Declaration { typ, name: name.into(), value, constant: false, span: Span::default() }
+}
+
 #[test]
 fn test_basic_parser() {
     let source = "int x = 2 * 2 + 2;\ny = 1;";
     fs::write(FILE_NAME, source).unwrap();
 
     let mut parser = Parser::new(FILE_NAME);
-    let symbols =  parser.parse();
+    let program = parser.parse().unwrap().without_spans();
 
-    let expected = vec![
-        SymbolNode::new(
-            SymbolKind::DeclarationAssignment,
-            ASTNode::DeclarationAssignment {
-                typ: Type::Integer,
-                name: Box::new(ASTNode::Identifier("x".into())),
-                value: Box::new(ASTNode::Expr(Box::new(ASTNode::BinaryOperation {
-                    left: Box::new(ASTNode::Expr(Box::new(ASTNode::BinaryOperation {
-                        left: Box::new(ASTNode::Expr(Box::new(ASTNode::Number(2)))),
-                        right: Box::new(ASTNode::Expr(Box::new(ASTNode::Number(2)))),
-                        operation: Box::new(ASTNode::Operator(OperatorType::Mul)),
-                    }))),
-                    right: Box::new(ASTNode::Expr(Box::new(ASTNode::Number(2)))),
-                    operation: Box::new(ASTNode::Operator(OperatorType::Add)),
+    let expected = Program {
+        items: vec![
+            Item::from(ItemKind::Global(declaration(
+                Type::Integer,
+                "x",
+                Some(binary(binary(number(2), BinOp::Mul, number(2)), BinOp::Add, number(2))),
+            ))),
+            Item::from(ItemKind::Statement(Stmt::from(StmtKind::Assign { target: identifier("y"), value: number(1) }))),
+        ],
+    };
+    assert_eq!(program, expected, "Parsed output does not match expected AST");
+}
+
+#[test]
+fn test_function_definition() {
+    let file_name = "test_parser_function.txt";
+    let source = "int add(int a, int b) {\n    int c = a + b;\n    c = c;\n}";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let expected = Program {
+        items: vec![Item::from(ItemKind::Function(Function {
+            return_type: Type::Integer,
+            name: "add".into(),
+            params: vec![
+                Param { typ: Type::Integer, name: "a".into(), span: Span::default() },
+                Param { typ: Type::Integer, name: "b".into(), span: Span::default() },
+            ],
+            body: vec![
+                Stmt::from(StmtKind::Declaration(declaration(
+                    Type::Integer,
+                    "c",
+                    Some(binary(identifier("a"), BinOp::Add, identifier("b"))),
+                ))),
+                Stmt::from(StmtKind::Assign { target: identifier("c"), value: identifier("c") }),
+            ],
+            span: Span::default(),
+        }))],
+    };
+    assert_eq!(program, expected, "Parsed function does not match expected AST");
+}
+
+#[test]
+fn test_call_and_return() {
+    let file_name = "test_parser_call.txt";
+    let source = "int main() {\n    f(a, b + 1);\n    return 2 + 3 * 4;\n}";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let expected = Program {
+        items: vec![Item::from(ItemKind::Function(Function {
+            return_type: Type::Integer,
+            name: "main".into(),
+            params: vec![],
+            body: vec![
+                Stmt::from(StmtKind::Expr(Expr::from(ExprKind::Call {
+                    name: "f".into(),
+                    args: vec![identifier("a"), binary(identifier("b"), BinOp::Add, number(1))],
                 }))),
-            },
-        ),
-        SymbolNode::new(
-            SymbolKind::Assign,
-            ASTNode::Assign {
-                name: Box::new(ASTNode::Identifier("y".into())),
-                value: Box::new(ASTNode::Expr(Box::new(ASTNode::Number(1)))),
-            },
-        ),
+                Stmt::from(StmtKind::Return(Some(binary(
+                    number(2),
+                    BinOp::Add,
+                    binary(number(3), BinOp::Mul, number(4)),
+                )))),
+            ],
+            span: Span::default(),
+        }))],
+    };
+    assert_eq!(program, expected, "Parsed calls do not match expected AST");
+}
+
+#[test]
+fn test_array_declaration_and_indexing() {
+    let file_name = "test_parser_array.txt";
+    let source = "int a[2][3];\na[i][1] = a[0][2] + 1;";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let index = |array: Expr, index: Expr| Expr::from(ExprKind::Index { array: Box::new(array), index: Box::new(index) });
+    let expected = Program {
+        items: vec![
+            Item::from(ItemKind::Global(declaration(Type::Array(Box::new(Type::Array(Box::new(Type::Integer), 3)), 2), "a", None))),
+            Item::from(ItemKind::Statement(Stmt::from(StmtKind::Assign {
+                target: index(index(identifier("a"), identifier("i")), number(1)),
+                value: binary(index(index(identifier("a"), number(0)), number(2)), BinOp::Add, number(1)),
+            }))),
+        ],
+    };
+    assert_eq!(program, expected, "Parsed arrays do not match expected AST");
+}
+
+#[test]
+fn test_struct_declaration_and_member_access() {
+    let file_name = "test_parser_struct.txt";
+    let source = "struct Point { int x; char tag; };\nstruct Point p;\np.x = p.x + 1;";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let member = |object: &str, field: &str| Expr::from(ExprKind::Member { object: Box::new(identifier(object)), field: field.into() });
+    let expected = Program {
+        items: vec![
+            Item::from(ItemKind::Struct {
+                name: "Point".into(),
+                fields: vec![
+                    Field { typ: Type::Integer, name: "x".into(), span: Span::default() },
+                    Field { typ: Type::Char, name: "tag".into(), span: Span::default() },
+                ],
+            }),
+            Item::from(ItemKind::Global(declaration(Type::Struct("Point".into()), "p", None))),
+            Item::from(ItemKind::Statement(Stmt::from(StmtKind::Assign {
+                target: member("p", "x"),
+                value: binary(member("p", "x"), BinOp::Add, number(1)),
+            }))),
+        ],
+    };
+    assert_eq!(program, expected, "Parsed struct does not match expected AST");
+}
+
+#[test]
+fn test_pointer_declaration_and_dereference() {
+    let file_name = "test_parser_pointer.txt";
+    let source = "int* p = &x;\n*p = *p * 2;";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let expected = Program {
+        items: vec![
+            Item::from(ItemKind::Global(declaration(
+                Type::Pointer(Box::new(Type::Integer)),
+                "p",
+                Some(unary(UnaryOp::AddressOf, identifier("x"))),
+            ))),
+            Item::from(ItemKind::Statement(Stmt::from(StmtKind::Assign {
+                target: unary(UnaryOp::Deref, identifier("p")),
+                value: binary(unary(UnaryOp::Deref, identifier("p")), BinOp::Mul, number(2)),
+            }))),
+        ],
+    };
+    assert_eq!(program, expected, "Parsed pointers do not match expected AST");
+}
+
+#[test]
+fn test_operators_are_left_associative() {
+    let file_name = "test_parser_associativity.txt";
+    let source = "int x = 8 - 4 - 2;";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+
+    let subtract = |left: Expr, right: Expr| binary(left, BinOp::Sub, right);
+    let expected = Program {
+        items: vec![Item::from(ItemKind::Global(declaration(
+            Type::Integer,
+            "x",
+            Some(subtract(subtract(number(8), number(4)), number(2))),
+        )))],
+    };
+    assert_eq!(program, expected, "(8 - 4) - 2 was not parsed as expected");
+}
+
+#[test]
+fn test_error_recovery_reports_every_error() {
+    let file_name = "test_parser_recovery.txt";
+    let source = "int x = ;\nint y = 2;\nint f() {\n    int a = 1 +;\n    return a;\n}\nint z = 3";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let (program, errors) = parser.parse_with_diagnostics();
+
+    let lines: Vec<u32> = errors.iter().map(ParseError::line).collect();
+    assert_eq!(lines, vec![1, 4, 7], "unexpected diagnostics: {:?}", errors);
+    let ParseError::UnexpectedToken { found, expected, .. } = &errors[0] else {
+        panic!("expected an unexpected token, got {:?}", errors[0]);
+    };
+    assert_eq!(found, "';'");
+    assert!(expected.contains(&"a number".to_string()));
+    assert!(matches!(errors[2], ParseError::UnexpectedEof { .. }), "the last error is the unexpected end of input");
+
+    let expected = Program {
+        items: vec![
+            Item::from(ItemKind::Error),
+            Item::from(ItemKind::Global(declaration(Type::Integer, "y", Some(number(2))))),
+            Item::from(ItemKind::Function(Function {
+                return_type: Type::Integer,
+                name: "f".into(),
+                params: vec![],
+                body: vec![Stmt::from(StmtKind::Error), Stmt::from(StmtKind::Return(Some(identifier("a"))))],
+                span: Span::default(),
+            })),
+        ],
+    };
+    assert_eq!(program.without_spans(), expected, "Recovered tree does not match expected AST");
+}
+
+#[test]
+fn test_only_terminals_that_can_follow_are_expected() {
+    let file_name = "test_parser_expected.txt";
+    fs::write(file_name, "int x = 5 % 2;").unwrap();
+
+    let errors = Parser::new(file_name).parse().unwrap_err();
+    let ParseError::UnexpectedToken { found, expected, .. } = &errors[0] else {
+        panic!("expected an unexpected token, got {:?}", errors[0]);
+    };
+    assert_eq!(found, "'%'");
+    // the lookaheads the state shares with other contexts, like ':' or ']', are left out
+    let after_initializer = ["'*'", "'+'", "'++'", "','", "'-'", "'--'", "'.'", "'/'", "';'", "'?'", "'['"];
+    assert_eq!(expected, &after_initializer);
+
+    fs::write(file_name, "int f() {\n    {\n    return 1;\n}").unwrap();
+    let errors = Parser::new(file_name).parse().unwrap_err();
+    let ParseError::UnexpectedToken { expected, .. } = &errors[0] else {
+        panic!("expected an unexpected token, got {:?}", errors[0]);
+    };
+    assert!(expected.contains(&"'return'".to_string()), "{:?}", expected);
+    for top_level in ["end of input", "'import'", "'pub'", "'typedef'"] {
+        assert!(!expected.contains(&top_level.to_string()), "{} is not expected in a function body", top_level);
+    }
+}
+
+#[test]
+fn test_unexpected_tokens_are_reported_as_written() {
+    let file_name = "test_parser_found.txt";
+    fs::write(file_name, "typedef int Id;\nint x = Id;\nint y = int;\nint z = 1 \"s\";").unwrap();
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let errors = parser.parse().unwrap_err();
+        let found: Vec<&str> = errors
+            .iter()
+            .map(|error| match error {
+                ParseError::UnexpectedToken { found, .. } => found.as_str(),
+                _ => panic!("expected an unexpected token, got {:?}", error),
+            })
+            .collect();
+        assert_eq!(found, ["`Id`", "`int`", "`\"s\"`"]);
+    }
+
+    // kinds of tokens are expected in words
+    let errors = Parser::new(file_name).parse().unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "Error at line 2, column 9: unexpected `Id`, expected '&', '(', '*', '++', '--', a bool, a character, a name, a number or a string"
+    );
+}
+
+#[test]
+fn test_trace_writes_to_the_given_sink() {
+    let file_name = "test_parser_trace.txt";
+    fs::write(file_name, "int x = 1;").unwrap();
+
+    let mut reductions = Vec::new();
+    let mut parser = Parser::new(file_name);
+    parser.enable_trace(TraceLevel::Reductions, Box::new(&mut reductions));
+    parser.parse().unwrap();
+    drop(parser);
+    let reductions = String::from_utf8(reductions).unwrap();
+    assert!(reductions.lines().any(|line| line == "line 1: reduce DeclarationHead -> TypeName Identifier"), "{}", reductions);
+    assert!(!reductions.contains("shift"), "shifts are only traced with the stack");
+
+    let mut stack = Vec::new();
+    let mut parser = Parser::new(file_name);
+    parser.enable_trace(TraceLevel::Stack, Box::new(&mut stack));
+    parser.parse().unwrap();
+    drop(parser);
+    let stack = String::from_utf8(stack).unwrap();
+    assert!(stack.contains("line 1: shift Identifier"), "{}", stack);
+    assert!(stack.len() > reductions.len());
+
+    let mut silent = Vec::new();
+    let mut parser = Parser::new(file_name);
+    parser.enable_trace(TraceLevel::Off, Box::new(&mut silent));
+    parser.parse().unwrap();
+    drop(parser);
+    assert!(silent.is_empty());
+}
+
+#[test]
+fn test_parse_returns_typed_errors() {
+    let file_name = "test_parser_errors.txt";
+    let source = "int x = 99999999999;\nfoo y;\nint a[0];\nint z = 1;\nint f() {\n    bar b = 2;\n    return 1;\n}";
+    fs::write(file_name, source).unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let errors = parser.parse().unwrap_err();
+
+    let expected = vec![
+        ParseError::InvalidLiteral { span: Span::new(1, 9, 1, 19), literal: "99999999999".into() },
+        ParseError::UnknownType { span: Span::new(2, 1, 2, 5), name: "foo".into() },
+        ParseError::InvalidArraySize { span: Span::at(3, 7), name: "a".into() },
+        ParseError::UnknownType { span: Span::new(6, 5, 6, 9), name: "bar".into() },
     ];
+    assert_eq!(errors, expected);
+    assert_eq!(errors[1].to_string(), "Error at line 2, column 1: Unknown type \"foo\"");
+}
+
+#[test]
+fn test_pratt_expressions_build_the_same_program() {
+    let file_name = "test_parser_pratt.txt";
+    let source = "int x = 1 - f(a, b[1]) * 2 - *p.y;";
+    fs::write(file_name, source).unwrap();
 
+    let mut parser = Parser::new(file_name);
+    parser.enable_pratt_expressions();
+    let program = parser.parse().unwrap();
 
+    let call = Expr::from(ExprKind::Call {
+        name: "f".into(),
+        args: vec![identifier("a"), Expr::from(ExprKind::Index { array: Box::new(identifier("b")), index: Box::new(number(1)) })],
+    });
+    let difference = binary(number(1), BinOp::Sub, binary(call, BinOp::Mul, number(2)));
+    let dereference = unary(UnaryOp::Deref, Expr::from(ExprKind::Member { object: Box::new(identifier("p")), field: "y".into() }));
+    let expected = Program {
+        items: vec![Item::from(ItemKind::Global(declaration(Type::Integer, "x", Some(binary(difference, BinOp::Sub, dereference)))))],
+    };
+    assert_eq!(program.clone().without_spans(), expected, "Pratt expression does not match expected AST");
+    // spans included, the nodes cover the same source
+    assert_eq!(Parser::new(file_name).parse().unwrap(), program, "both parsers should build the same program");
+}
 
+#[test]
+fn test_pratt_expressions_report_the_same_errors() {
+    let file_name = "test_parser_pratt_errors.txt";
+    let source = "int x = 1 +;\nint y = 2;\nfoo z;\nint f() {\n    int a = (1;\n    return a;\n}";
+    fs::write(file_name, source).unwrap();
+
+    let mut table = Parser::new(file_name);
+    let (table_program, table_errors) = table.parse_with_diagnostics();
+    let mut pratt = Parser::new(file_name);
+    pratt.enable_pratt_expressions();
+    let (pratt_program, pratt_errors) = pratt.parse_with_diagnostics();
+
+    let lines: Vec<u32> = pratt_errors.iter().map(ParseError::line).collect();
+    assert_eq!(lines, vec![1, 3, 5], "unexpected diagnostics: {:?}", pratt_errors);
+    assert_eq!(pratt_errors, table_errors);
+    assert_eq!(pratt_program, table_program);
+}
 
+#[test]
+fn test_compound_assignment_and_update() {
+    let file_name = "test_parser_compound.txt";
+    let source = "int f() {\n    x <<= a[i++] + 1;\n    --*p;\n}";
+    fs::write(file_name, source).unwrap();
+
+    let update = |operation: UpdateOp, operand: Expr, prefix: bool| Expr::from(ExprKind::Update { operation, operand: Box::new(operand), prefix });
+    let index = Expr::from(ExprKind::Index {
+        array: Box::new(identifier("a")),
+        index: Box::new(update(UpdateOp::Increment, identifier("i"), false)),
+    });
+    let body = vec![
+        Stmt::from(StmtKind::CompoundAssign {
+            target: identifier("x"),
+            operation: AssignOp::ShiftLeft,
+            value: binary(index, BinOp::Add, number(1)),
+        }),
+        Stmt::from(StmtKind::Expr(update(UpdateOp::Decrement, unary(UnaryOp::Deref, identifier("p")), true))),
+    ];
 
-    assert_eq!(*symbols, expected, "Parsed output does not match expected AST");
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap().without_spans();
+    let ItemKind::Function(function) = &program.items[0].kind else {
+        panic!("expected a function, got {:?}", program.items[0]);
+    };
+    assert_eq!(function.body, body);
+}
 
+#[test]
+fn test_conditional_is_right_associative_and_binds_loosest() {
+    let file_name = "test_parser_conditional.txt";
+    fs::write(file_name, "int x = a ? b + 1 : c ? d : e * 2;").unwrap();
+
+    let conditional = |condition: Expr, if_true: Expr, if_false: Expr| Expr::from(ExprKind::Conditional {
+        condition: Box::new(condition),
+        if_true: Box::new(if_true),
+        if_false: Box::new(if_false),
+    });
+    let inner = conditional(identifier("c"), identifier("d"), binary(identifier("e"), BinOp::Mul, number(2)));
+    let outer = conditional(identifier("a"), binary(identifier("b"), BinOp::Add, number(1)), inner);
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap().without_spans();
+        let ItemKind::Global(Declaration { value: Some(value), .. }) = &program.items[0].kind else {
+            panic!("expected a declaration, got {:?}", program.items[0]);
+        };
+        assert_eq!(value, &outer);
+        assert_eq!(value.to_string(), "Conditional(a, Binary(b, Add, 1), Conditional(c, d, Binary(e, Mul, 2)))");
+    }
+}
+
+#[test]
+fn test_switch_collects_statements_into_cases() {
+    let file_name = "test_parser_switch.txt";
+    fs::write(file_name, "int f(int x) {\n switch (x) {\n case 1:\n case 2:\n x = 3;\n break;\n default:\n return x;\n }\n return 0;\n}").unwrap();
 
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap();
+        let ItemKind::Function(function) = &program.items[0].kind else {
+            panic!("expected a function, got {:?}", program.items[0]);
+        };
+        let StmtKind::Switch { cases, .. } = &function.body[0].kind else {
+            panic!("expected a switch, got {:?}", function.body[0]);
+        };
+        assert_eq!(cases.len(), 3);
+        assert!(matches!(&cases[0], Case { value: Some(_), body, .. } if body.is_empty()));
+        assert!(matches!(&cases[1], Case { value: Some(_), body, .. } if matches!(body.as_slice(), [_, Stmt { kind: StmtKind::Break, .. }])));
+        assert!(matches!(&cases[2], Case { value: None, body, .. } if body.len() == 1));
+    }
+}
+
+#[test]
+fn test_declarator_lists_share_the_type_before_the_first_name() {
+    let file_name = "test_parser_declarators.txt";
+    fs::write(file_name, "int* a[2], b = 0, c[3];\nconst char d = 'x', e = 'y';").unwrap();
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap();
+        let items: Vec<String> = program.items.iter().map(|item| item.to_string()).collect();
+        assert_eq!(
+            items,
+            vec![
+                "Declaration(Array(Pointer(Integer), 2), a)",
+                "Declaration(Pointer(Integer), b, 0)",
+                "Declaration(Array(Pointer(Integer), 3), c)",
+                "ConstDeclaration(Char, d, 'x')",
+                "ConstDeclaration(Char, e, 'y')",
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_cast_binds_like_a_prefix_operator() {
+    let file_name = "test_parser_cast.txt";
+    fs::write(file_name, "int x = (int) c * 2 + (int) a[1];\nchar* p = (char*) (struct Point*) q;").unwrap();
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap();
+        let items: Vec<String> = program.items.iter().map(|item| item.to_string()).collect();
+        assert_eq!(
+            items,
+            vec![
+                "Declaration(Integer, x, Binary(Binary(Cast(Integer, c), Mul, 2), Add, Cast(Integer, Index(a, 1))))",
+                "Declaration(Pointer(Char), p, Cast(Pointer(Char), Cast(Pointer(Struct(\"Point\")), q)))",
+            ]
+        );
+    }
+
+    let source = "int x = (int 1;\nint y = (;\nint z = (struct 2) w;";
+    fs::write(file_name, source).unwrap();
+    let (_, table_errors) = Parser::new(file_name).parse_with_diagnostics();
+    let mut pratt = Parser::new(file_name);
+    pratt.enable_pratt_expressions();
+    let (_, pratt_errors) = pratt.parse_with_diagnostics();
+    assert_eq!(table_errors.len(), 3, "unexpected diagnostics: {:?}", table_errors);
+    assert_eq!(pratt_errors, table_errors);
+}
+
+#[test]
+fn test_enum_variants_fold_as_constants() {
+    let file_name = "test_parser_enum.txt";
+    fs::write(file_name, "enum Color { Red, Green = 5, Blue, };\nenum Color c = (enum Color) 2;").unwrap();
+
+    let mut parser = Parser::new(file_name);
+    let program = parser.parse().unwrap();
+    assert!(matches!(program.items[0].kind, ItemKind::Enum { .. }));
+    assert_eq!(program.items[0].to_string(), "Enum(Color, [Red, Green = 5, Blue])");
+    assert_eq!(program.items[1].to_string(), "Declaration(Enum(\"Color\"), c, Cast(Enum(\"Color\"), 2))");
+
+    let ItemKind::Global(Declaration { value: Some(value), .. }) = &program.items[1].kind else {
+        panic!("expected a declaration, got {:?}", program.items[1]);
+    };
+    assert_eq!(value.evaluate_constant(), Some(2));
+
+    let constants = [("Green".to_string(), 5)].into_iter().collect();
+    let green_plus_one = binary(identifier("Green"), BinOp::Add, number(1));
+    assert_eq!(green_plus_one.evaluate_constant(), None);
+    assert_eq!(green_plus_one.evaluate_constant_in(&constants), Some(6));
+}
+
+#[test]
+fn test_typedef_names_are_read_as_types() {
+    let file_name = "test_parser_typedef.txt";
+    let source = "typedef int Id;\nId x = (Id) 1;\ntypedef struct Point Point;\nstruct Point p;\nPoint* q;";
+    fs::write(file_name, source).unwrap();
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap().without_spans();
+        assert_eq!(program.items.len(), 5);
+        assert_eq!(program.items[0], Item::from(ItemKind::TypeAlias { name: "Id".into(), typ: Type::Integer }));
+        assert_eq!(program.items[2], Item::from(ItemKind::TypeAlias { name: "Point".into(), typ: Type::Struct("Point".into()) }));
+        // the alias is resolved where it is used, in declarations and casts alike
+        let ItemKind::Global(Declaration { typ, value: Some(value), .. }) = &program.items[1].kind else {
+            panic!("expected a declaration, got {:?}", program.items[1]);
+        };
+        assert_eq!(typ, &Type::Integer);
+        assert_eq!(value, &Expr::from(ExprKind::Cast { typ: Type::Integer, operand: Box::new(number(1)) }));
+        assert_eq!(program.items[3], Item::from(ItemKind::Global(declaration(Type::Struct("Point".into()), "p", None))));
+        assert_eq!(program.items[4], Item::from(ItemKind::Global(declaration(Type::Pointer(Box::new(Type::Struct("Point".into()))), "q", None))));
+    }
+}
+
+#[test]
+fn test_nodes_span_the_source_they_are_read_from() {
+    let file_name = "test_parser_spans.txt";
+    fs::write(file_name, "int f(int a) {\n    int x = (a + 1) * 2;\n    return x;\n}").unwrap();
+    let at = |span: Span| (span.line, span.column, span.end_line, span.end_column);
+
+    for pratt in [false, true] {
+        let mut parser = Parser::new(file_name);
+        if pratt {
+            parser.enable_pratt_expressions();
+        }
+        let program = parser.parse().unwrap();
+        let ItemKind::Function(function) = &program.items[0].kind else {
+            panic!("expected a function, got {:?}", program.items[0]);
+        };
+        assert_eq!(at(program.items[0].span), (1, 1, 4, 1));
+        assert_eq!(at(function.params[0].span), (1, 7, 1, 11));
+
+        let StmtKind::Declaration(Declaration { value: Some(value), .. }) = &function.body[0].kind else {
+            panic!("expected a declaration, got {:?}", function.body[0]);
+        };
+        assert_eq!(at(function.body[0].span), (2, 5, 2, 24));
+        assert_eq!(at(value.span), (2, 13, 2, 23));
+        // a parenthesized operand spans its parentheses
+        let ExprKind::Binary { left, right, .. } = &value.kind else {
+            panic!("expected a binary operation, got {}", value);
+        };
+        assert_eq!(at(left.span), (2, 13, 2, 19));
+        assert_eq!(at(right.span), (2, 23, 2, 23));
+        assert_eq!(at(function.body[1].span), (3, 5, 3, 13));
+    }
+}
+
+#[test]
+fn test_visitors_reach_every_expression() {
+    let file_name = "test_parser_visit.txt";
+    fs::write(file_name, "int g = 1;\nint f(int a) {\n    int x = a + g;\n    switch (x) {\n        case 1: x = f(g * 2);\n    }\n    return x;\n}").unwrap();
+    let mut program = Parser::new(file_name).parse().unwrap();
 
+    struct Identifiers(Vec<String>);
+    impl Visitor for Identifiers {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let ExprKind::Identifier(name) = &expr.kind {
+                self.0.push(name.clone());
+            }
+            walk_expr(self, expr);
+        }
+    }
+    let mut identifiers = Identifiers(Vec::new());
+    identifiers.visit_program(&program);
+    assert_eq!(identifiers.0, ["a", "g", "x", "x", "g", "x"]);
 
-}
\ No newline at end of file
+    // a rewrite only overrides the nodes it changes, the walk reaches the others
+    struct Rename;
+    impl VisitorMut for Rename {
+        fn visit_expr_mut(&mut self, expr: &mut Expr) {
+            if let ExprKind::Identifier(name) = &mut expr.kind
+                && name == "g"
+            {
+                *name = "h".into();
+            }
+            walk_expr_mut(self, expr);
+        }
+    }
+    Rename.visit_program_mut(&mut program);
+    let mut identifiers = Identifiers(Vec::new());
+    identifiers.visit_program(&program);
+    assert_eq!(identifiers.0, ["a", "h", "x", "x", "h", "x"]);
+}
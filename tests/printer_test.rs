@@ -0,0 +1,102 @@
+use std::fs;
+use compiler::parser::parser::Parser;
+use compiler::parser::printer::{print_expr, print_program};
+use compiler::parser::program::{ItemKind, Program, StmtKind};
+
+const SOURCE: &str = "import \"math.src\";
+typedef int Id;
+typedef struct Point Point;
+typedef char Name[16];
+struct Point { int x, y; Name label; };
+enum Color { Red, Green = 5, Blue };
+enum Empty {};
+const int limit = 2 * (3 + 4), other;
+pub Id next(Id id) { return id + 1; }
+string greeting = \"hello world\";
+int grid[2][3];
+int* p;
+x = 1;
+int f(Point* q, int a[4], char c) {
+    int total = (a[0] + a[1]) * 2;
+    Point copy; copy.x = (*q).x; copy.label[0] = 'z';
+    total += (int) c; total <<= 1; total %= 7;
+    switch (total) { case 1: case 2: total = total - (1 - 2); break; default: total = 0; }
+    *p++; (*p)++; ++*p; --a[0];
+    int picked = total ? a[0] : c ? 1 : 2;
+    int nested = (total ? a[0] : c) ? next(total, p) : (bool) (struct Point*) &copy;
+    return f(q, a, c) / (2 / 3) - (total - 1);
+}
+";
+
+fn parse(file_name: &str, source: &str, pratt: bool) -> Program {
+    fs::write(file_name, source).unwrap();
+    let mut parser = Parser::new(file_name);
+    if pratt {
+        parser.enable_pratt_expressions();
+    }
+    parser.parse().unwrap_or_else(|errors| panic!("{:?} in\n{}", errors, source))
+}
+
+#[test]
+fn test_printed_programs_parse_to_the_same_program() {
+    for pratt in [false, true] {
+        let program = parse("test_printer_round_trip.txt", SOURCE, pratt);
+        let printed = print_program(&program);
+        let reparsed = parse("test_printer_round_trip.txt", &printed, pratt);
+        // the layout differs, so only the spans may
+        assert_eq!(reparsed.clone().without_spans(), program.without_spans(), "printed as\n{}", printed);
+        // the layout is canonical, printing again gives the same text
+        assert_eq!(print_program(&reparsed), printed);
+    }
+}
+
+#[test]
+fn test_programs_are_printed_in_canonical_layout() {
+    let source = "int f(int a){int b=a;switch(b){case 1:b+=1;break;default:return b;}return(b);}\nint g;\nint h;pub struct P{int x;};";
+    let program = parse("test_printer_layout.txt", source, false);
+
+    let expected = "int f(int a) {
+    int b = a;
+    switch (b) {
+        case 1:
+            b += 1;
+            break;
+        default:
+            return b;
+    }
+    return b;
+}
+
+int g;
+int h;
+
+pub struct P {
+    int x;
+};
+";
+    assert_eq!(print_program(&program), expected);
+}
+
+#[test]
+fn test_only_needed_parentheses_are_printed() {
+    let source = "int x = ((a + (b * c)) - (d - (e))) / (f / g);
+int y = (((a ? b : c)) ? (d ? e : f) : (g ? h : i));
+int z = (*(p.q))++ + *(p++) + (*p)[1] + ((int) (a[2]));";
+    let program = parse("test_printer_parentheses.txt", source, false);
+    let values: Vec<String> = program
+        .items
+        .iter()
+        .map(|item| match &item.kind {
+            ItemKind::Global(declaration) => print_expr(declaration.value.as_ref().unwrap()),
+            ItemKind::Statement(statement) => match &statement.kind {
+                StmtKind::Expr(expr) => print_expr(expr),
+                _ => panic!("expected an expression, got {}", statement),
+            },
+            _ => panic!("expected a declaration, got {}", item),
+        })
+        .collect();
+
+    assert_eq!(values[0], "(a + b * c - (d - e)) / (f / g)");
+    assert_eq!(values[1], "(a ? b : c) ? d ? e : f : g ? h : i");
+    assert_eq!(values[2], "(*p.q)++ + *p++ + (*p)[1] + (int) a[2]");
+}
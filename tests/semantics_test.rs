@@ -0,0 +1,129 @@
+use std::fs;
+use compiler::parser::parser::Parser;
+use compiler::parser::span::Span;
+use compiler::semantics::error::SemanticError;
+use compiler::semantics::semantics::Semantics;
+
+fn check(file_name: &str, source: &str) -> Result<(), SemanticError> {
+    fs::write(file_name, source).unwrap();
+    let program = Parser::new(file_name).parse().unwrap_or_else(|errors| panic!("{:?} in\n{}", errors, source));
+    Semantics::new(&program).validate_semantics()
+}
+
+/// The error the source is rejected with
+fn rejected(file_name: &str, source: &str) -> SemanticError {
+    match check(file_name, source) {
+        Ok(()) => panic!("expected an error in\n{}", source),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn test_switch_cases_are_checked() {
+    let file_name = "test_semantics_switch.txt";
+    let valid = "int f(int x) {\n    switch (x) {\n        case 1:\n        case 2:\n            x = 0;\n            break;\n        default:\n            x = 1;\n    }\n    return x;\n}";
+    assert_eq!(check(file_name, valid), Ok(()));
+
+    let duplicate = rejected(file_name, "int f(int x) {\n    switch (x) {\n        case 1: break;\n        case 2 - 1: break;\n    }\n    return x;\n}");
+    assert_eq!(duplicate, SemanticError::new(Span::new(4, 14, 4, 18), "semantics failed! duplicate case Binary(2, Sub, 1)"));
+
+    let defaults = rejected(file_name, "int f(int x) {\n    switch (x) { default: break; default: break; }\n    return x;\n}");
+    assert_eq!(defaults.message, "semantics failed! switch over x has more than one default");
+
+    let fall_through = rejected(file_name, "int f(int x) {\n    switch (x) { case 1: x = 2; case 2: break; }\n    return x;\n}");
+    assert_eq!(fall_through.message, "semantics failed! case 1 falls through, end it with a break");
+
+    let constant = rejected(file_name, "int f(int x) {\n    switch (x) { case x: break; }\n    return x;\n}");
+    assert_eq!(constant.message, "semantics failed! case x is not a constant");
+
+    let outside = rejected(file_name, "int f(int x) {\n    break;\n    return x;\n}");
+    assert_eq!(outside, SemanticError::new(Span::new(2, 5, 2, 10), "semantics failed! break outside of a switch"));
+}
+
+#[test]
+fn test_calls_are_checked_against_the_signature() {
+    let file_name = "test_semantics_calls.txt";
+    let function = "int add(int a, char b) {\n    return a;\n}\n";
+
+    let count = rejected(file_name, &format!("{}int main() {{\n    return add(1);\n}}", function));
+    assert_eq!(count, SemanticError::new(Span::new(5, 12, 5, 17), "function add takes 2 arguments but 1 were given"));
+
+    let argument = rejected(file_name, &format!("{}int main() {{\n    return add(1, true);\n}}", function));
+    assert_eq!(argument.span, Span::new(5, 19, 5, 22));
+    assert_eq!(argument.message, "argument 2 of add has the wrong type\n\tExpected: char\n\tGot: bool");
+
+    // types are named as they are written
+    let pointer = "struct Point { int x; };\nint take(int* a) {\n    return *a;\n}\nint main() {\n    struct Point* p;\n    return take(p);\n}";
+    assert_eq!(rejected(file_name, pointer).message, "argument 1 of take has the wrong type\n\tExpected: int*\n\tGot: struct Point*");
+    let array = rejected(file_name, "int take(int a[4]) {\n    return a[0];\n}");
+    assert_eq!(array.message, "semantics failed! a of type int[4] cannot be passed to take by value");
+
+    let undeclared = rejected(file_name, "int main() {\n    return sub(1);\n}");
+    assert_eq!(undeclared.message, "function sub is not declared");
+
+    let value = rejected(file_name, "int main() {\n    return true;\n}");
+    assert_eq!(value.message, "return type mismatch\n\tExpected: int\n\tGot: bool");
+
+    let missing = rejected(file_name, "int main() {\n    return;\n}");
+    assert_eq!(missing.message, "semantics failed! missing return value of type int");
+}
+
+#[test]
+fn test_conditional_needs_a_bool_and_arms_of_one_type() {
+    let file_name = "test_semantics_conditional.txt";
+    assert_eq!(check(file_name, "int main() {\n    char c = 'a';\n    return true ? c : 2;\n}"), Ok(()));
+
+    let condition = rejected(file_name, "int main() {\n    return 1 ? 2 : 3;\n}");
+    assert_eq!(condition, SemanticError::new(Span::at(2, 12), "semantics failed! condition 1 must be a Bool"));
+
+    let arms = rejected(file_name, "int main() {\n    int x = true ? 1 : false;\n    return x;\n}");
+    assert_eq!(arms.message, "mismatched types in the arms of Conditional(true, 1, false)\n\tTrue: int\n\tFalse: bool");
+}
+
+#[test]
+fn test_constants_are_never_written() {
+    let file_name = "test_semantics_constants.txt";
+    let constant = "const int limit = 3;\n";
+    assert_eq!(check(file_name, &format!("{}int main() {{\n    return limit + 1;\n}}", constant)), Ok(()));
+
+    for write in ["limit = 4;", "limit += 1;", "limit++;"] {
+        let error = rejected(file_name, &format!("{}int main() {{\n    {}\n    return 0;\n}}", constant, write));
+        assert_eq!(error.message, "semantics failed! constant limit cannot be written", "{}", write);
+        assert_eq!(error.span.line, 3, "{}", write);
+    }
+
+    let address = rejected(file_name, &format!("{}int main() {{\n    int* p = &limit;\n    return 0;\n}}", constant));
+    assert_eq!(address.message, "semantics failed! cannot take the address of constant limit");
+
+    let uninitialized = rejected(file_name, "const int limit;");
+    assert_eq!(uninitialized.message, "semantics failed! constant limit needs an initializer");
+}
+
+#[test]
+fn test_enum_values_widen_to_int() {
+    let file_name = "test_semantics_enums.txt";
+    let color = "enum Color { Red, Green = 5, Blue };\n";
+    let widened = "int x = Green + 1;\nint y = Green;\nint f(int n) {\n    int a[8];\n    a[Blue] = Red * 2;\n    enum Color c = Blue;\n    n += c;\n    return f(Green) - c;\n}";
+    assert_eq!(check(file_name, &format!("{}{}", color, widened)), Ok(()));
+
+    // an enum variable still only takes values of its enum
+    let assigned = rejected(file_name, &format!("{}enum Color c = 1;", color));
+    assert_eq!(assigned.message, "type mismatch\n\tExpected: enum Color\n\tGot: int");
+    let compound = rejected(file_name, &format!("{}int f() {{\n    enum Color c = Red;\n    c += 1;\n    return 0;\n}}", color));
+    assert_eq!(compound.message, "operator Add cannot be applied to enum Color");
+}
+
+#[test]
+fn test_a_switch_returning_in_every_case_returns() {
+    let file_name = "test_semantics_returns.txt";
+    let returning = "int f(int x) {\n    switch (x) {\n        case 1: return 1;\n        case 2:\n        case 3: return 3;\n        default: return 2;\n    }\n}";
+    assert_eq!(check(file_name, returning), Ok(()));
+
+    let missing = "semantics failed! function f must return a value of type int";
+    let without_default = "int f(int x) {\n    switch (x) { case 1: return 1; case 2: return 2; }\n}";
+    assert_eq!(rejected(file_name, without_default).message, missing);
+    let breaking = "int f(int x) {\n    switch (x) { case 1: break; default: return 2; }\n}";
+    assert_eq!(rejected(file_name, breaking).message, missing);
+    let falling_out = "int f(int x) {\n    switch (x) { case 1: return 1; default: }\n}";
+    assert_eq!(rejected(file_name, falling_out).message, missing);
+}
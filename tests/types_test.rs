@@ -0,0 +1,40 @@
+use compiler::parser::types::{StructLayout, StructTable, Type, TypeTable};
+
+#[test]
+fn test_struct_layout() {
+    let mut structs = StructTable::new();
+    let point = StructLayout::new(
+        &[("tag".into(), Type::Char), ("x".into(), Type::Integer), ("y".into(), Type::Integer)],
+        &structs,
+    );
+    structs.insert("Point".into(), point);
+
+    let line = StructLayout::new(
+        &[
+            ("from".into(), Type::Struct("Point".into())),
+            ("visible".into(), Type::Bool),
+            ("weights".into(), Type::Array(Box::new(Type::Integer), 3)),
+            ("flags".into(), Type::Array(Box::new(Type::Char), 3)),
+        ],
+        &structs,
+    );
+
+    let offsets: Vec<i32> = line.fields.iter().map(|field| field.offset).collect();
+    assert_eq!(offsets, vec![0, 12, 16, 28], "Field offsets do not follow their alignment");
+    assert_eq!(line.size, 32, "Struct size is not padded to its alignment");
+    assert_eq!(line.alignment, 4);
+    assert_eq!(Type::Struct("Line".into()).to_size_asm(&structs), 0, "Undeclared struct has a size");
+}
+
+#[test]
+fn test_type_table_aliases() {
+    let mut types = TypeTable::new();
+    assert_eq!(types.get("int"), Some(&Type::Integer));
+    assert_eq!(types.get("Id"), None);
+
+    assert!(types.add_alias("Id", Type::Integer));
+    assert_eq!(types.get("Id"), Some(&Type::Integer));
+    assert!(!types.add_alias("Id", Type::Char), "an alias was declared twice");
+    assert!(!types.add_alias("char", Type::Integer), "a built-in type was redeclared");
+    assert_eq!(types.get("char"), Some(&Type::Char));
+}